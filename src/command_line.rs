@@ -0,0 +1,227 @@
+use crate::config;
+use crate::mi::escape;
+use crate::tokens::COMPLETE;
+
+/// Commands kept in the history file
+const MAX_HISTORY: usize = 500;
+
+/// The console's input: what's being typed, the commands typed before and the completions GDB
+/// offered
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CommandLine {
+    pub input: String,
+    /// Oldest first
+    pub history: Vec<String>,
+    /// Where up and down are in `history`, `None` when typing a new command
+    position: Option<usize>,
+    /// What was being typed before going up the history
+    draft: String,
+    /// Plain CLI commands are run through `-interpreter-exec console`
+    pub cli: bool,
+    /// Every command GDB could complete the input to, after a tab
+    pub matches: Vec<String>,
+    /// Breakpoints typed in are inserted as hardware ones, as the kernel sessions want
+    pub hardware: bool,
+}
+
+impl CommandLine {
+    /// With the history of the previous sessions
+    pub fn load() -> CommandLine {
+        let history = config::read("history")
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        CommandLine {
+            history,
+            cli: true,
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let mut s = self.history.join("\n");
+        s.push('\n');
+        config::write("history", &s)
+    }
+
+    /// Takes the input, puts it in the history and returns the command to send
+    pub fn submit(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.input).trim().to_string();
+        self.position = None;
+        self.matches.clear();
+        if line.is_empty() {
+            return None;
+        }
+
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            let extra = self.history.len().saturating_sub(MAX_HISTORY);
+            self.history.drain(..extra);
+
+            if let Err(e) = self.save() {
+                println!("Failed to save the command history: {e}");
+            }
+        }
+
+        Some(self.command(&line))
+    }
+
+    /// Under MI the output of CLI commands is only captured when MI runs them, MI commands go as
+    /// they are
+    fn command(&self, line: &str) -> String {
+        let line = if self.hardware {
+            hardware_breakpoint(line)
+        } else {
+            line.to_string()
+        };
+
+        if self.cli && !line.starts_with('-') {
+            format!("-interpreter-exec console \"{}\"", escape(&line))
+        } else {
+            line
+        }
+    }
+
+    pub fn up(&mut self) {
+        let position = match self.position {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.input.clone();
+                self.history.len() - 1
+            }
+            Some(p) => p.saturating_sub(1),
+        };
+
+        self.position = Some(position);
+        self.input = self.history[position].clone();
+    }
+
+    pub fn down(&mut self) {
+        match self.position {
+            Some(p) if p + 1 < self.history.len() => {
+                self.position = Some(p + 1);
+                self.input = self.history[p + 1].clone();
+            }
+            Some(_) => {
+                self.position = None;
+                self.input = std::mem::take(&mut self.draft);
+            }
+            None => {}
+        }
+    }
+
+    /// `-complete` knows the CLI commands, not the MI ones
+    pub fn complete_command(&self) -> Option<String> {
+        if self.input.starts_with('-') {
+            return None;
+        }
+
+        Some(format!("{COMPLETE}-complete \"{}\"", escape(&self.input)))
+    }
+
+    /// GDB's answer to `complete_command`, `completion` is the whole line up to where the matches
+    /// differ
+    pub fn completed(&mut self, completion: Option<String>, matches: Vec<String>) {
+        if let Some(c) = completion {
+            self.input = c;
+        }
+        self.matches = if matches.len() > 1 { matches } else { vec![] };
+    }
+}
+
+/// `break` as `hbreak`, `tbreak` as `thbreak` and `-break-insert` with `-h`, anything else as it is
+fn hardware_breakpoint(line: &str) -> String {
+    let (cmd, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+    let cmd = match cmd {
+        "b" | "br" | "bre" | "brea" | "break" => "hbreak",
+        "tb" | "tbr" | "tbre" | "tbrea" | "tbreak" => "thbreak",
+        "-break-insert" if !rest.split_whitespace().any(|a| a == "-h") => "-break-insert -h",
+        _ => return line.to_string(),
+    };
+
+    if rest.is_empty() {
+        cmd.to_string()
+    } else {
+        format!("{cmd} {rest}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut line = CommandLine {
+            history: vec!["info registers".into(), "bt".into()],
+            ..Default::default()
+        };
+
+        line.input = "print x".into();
+        line.up();
+        assert_eq!(line.input, "bt");
+        line.up();
+        line.up();
+        assert_eq!(line.input, "info registers");
+        line.down();
+        assert_eq!(line.input, "bt");
+        line.down();
+        assert_eq!(line.input, "print x");
+        line.down();
+        assert_eq!(line.input, "print x");
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut line = CommandLine {
+            cli: true,
+            ..Default::default()
+        };
+
+        line.input = "print \"hi\"".into();
+        assert_eq!(
+            line.command(&line.input),
+            r#"-interpreter-exec console "print \"hi\"""#
+        );
+        assert_eq!(line.command("-stack-list-frames"), "-stack-list-frames");
+        line.cli = false;
+        assert_eq!(line.command("info frame"), "info frame");
+
+        line.input = "br".into();
+        assert_eq!(
+            line.complete_command().as_deref(),
+            Some(r#"7-complete "br""#)
+        );
+        line.completed(
+            Some("break".into()),
+            vec!["break".into(), "break-range".into()],
+        );
+        assert_eq!(line.input, "break");
+        assert_eq!(line.matches.len(), 2);
+    }
+
+    #[test]
+    fn test_hardware() {
+        let line = CommandLine {
+            cli: true,
+            hardware: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            line.command("b start_kernel"),
+            r#"-interpreter-exec console "hbreak start_kernel""#
+        );
+        assert_eq!(
+            line.command("tbreak init/main.c:900"),
+            r#"-interpreter-exec console "thbreak init/main.c:900""#
+        );
+        assert_eq!(
+            line.command("-break-insert -t do_fork"),
+            "-break-insert -h -t do_fork"
+        );
+        assert_eq!(line.command("-break-insert -h x"), "-break-insert -h x");
+        assert_eq!(line.command("bt"), r#"-interpreter-exec console "bt""#);
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A configuration saved as `key=value` lines, each type only says which field goes with which key
+pub trait KeyValues {
+    /// In the order they are written, a key can come more than once
+    fn entries(&self) -> Vec<(&'static str, String)>;
+    /// Takes the value of a saved key, the ones it doesn't know are ignored
+    fn set(&mut self, key: &str, value: &str);
+}
+
+/// `~/.config/iron-dbg/name`, where everything kept between sessions goes
+pub fn path(name: impl AsRef<Path>) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+
+    Some(Path::new(&home).join(".config").join("iron-dbg").join(name))
+}
+
+/// The contents of the file `name`, `None` if it was never written
+pub fn read(name: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path(name)?).ok()
+}
+
+pub fn write(name: impl AsRef<Path>, contents: &str) -> io::Result<()> {
+    let p =
+        path(name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "$HOME is not set"))?;
+
+    if let Some(dir) = p.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(p, contents)
+}
+
+/// `default` with what was saved as `name` on top of it
+pub fn load<C: KeyValues>(name: impl AsRef<Path>, default: C) -> C {
+    match read(name) {
+        Some(src) => parse(default, &src),
+        None => default,
+    }
+}
+
+pub fn save(name: impl AsRef<Path>, config: &impl KeyValues) -> io::Result<()> {
+    write(name, &serialize(config))
+}
+
+pub fn serialize(config: &impl KeyValues) -> String {
+    config
+        .entries()
+        .iter()
+        .map(|(k, v)| format!("{k}={v}\n"))
+        .collect()
+}
+
+pub fn parse<C: KeyValues>(mut config: C, src: &str) -> C {
+    for (k, v) in src.lines().filter_map(|l| l.split_once('=')) {
+        config.set(k, v);
+    }
+
+    config
+}
+
+/// An input field, or `default` when it was left empty
+pub fn field_or(field: &str, default: String) -> String {
+    match field.trim() {
+        "" => default,
+        s => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_or() {
+        assert_eq!(field_or("  ", "1234".to_string()), "1234");
+        assert_eq!(field_or(" 2345 ", "1234".to_string()), "2345");
+    }
+}
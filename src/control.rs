@@ -2,20 +2,67 @@ use crate::mi_parse::MIRepr;
 use crate::Arc;
 use crate::ConsoleOutput;
 use crate::ControlState::{AttachFileDialog, SendCommand};
-use crate::{mi, query};
+use crate::{
+    disassembly, gdbserver, inferiors, kernel, memory, mi, procs, qemu, query, registers, repl,
+    report, tokens, varobj, watch,
+};
 use snailquote::unescape;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{write, Formatter};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use crate::launch::LaunchConfig;
 use crate::mi::{parse_stream, Output};
 use crate::mi_types;
 use static_init::dynamic;
 
-const START_COMMANDS: &[&'static str] = &["set disassembly-flavor intel"];
-const STEP_COMMANDS: &[&'static str] = &["-exec-step", "-stack-list-frames"];
+/// `mi-async` has to be set before the target starts running, so these go before `file`/`target`
+const START_COMMANDS: &[&str] = &["-gdb-set mi-async on", "set disassembly-flavor intel"];
+const STEP_COMMANDS: &[&str] = &["-exec-step"];
+const NEXT_COMMANDS: &[&str] = &["-exec-next"];
+const FINISH_COMMANDS: &[&str] = &["-exec-finish"];
+const STEPI_COMMANDS: &[&str] = &["-exec-step-instruction"];
+const NEXTI_COMMANDS: &[&str] = &["-exec-next-instruction"];
+const UNTIL_COMMANDS: &[&str] = &["-exec-until"];
+const CONTINUE_COMMANDS: &[&str] = &["-exec-continue"];
+const INTERRUPT_COMMANDS: &[&str] = &["-exec-interrupt"];
+const KILL_COMMANDS: &[&str] = &["kill"];
+/// Breakpoints are kept by GDB, so running again is enough to restart the session
+const RESTART_COMMANDS: &[&str] = &["kill", "-exec-run --start"];
+const RUN_AGAIN_COMMANDS: &[&str] = &["-exec-run --start"];
+/// Leaves the process running, unlike `kill`
+const DETACH_COMMANDS: &[&str] = &["-target-detach"];
+/// Starts walking every thread and frame, see `collect_commands`
+const COLLECT_COMMANDS: &[&str] = &["-thread-info", "-thread-list-ids"];
+/// What the crash report needs besides the backtraces and locals, see `report`
+const REPORT_COMMANDS: &[&str] = &[
+    "-data-list-register-names",
+    "-data-list-register-values x",
+    // The whole function, a range starting before `$pc` may start in the middle of an instruction
+    "-data-disassemble -a \"$pc\" -- 0",
+    "-interpreter-exec console \"info proc mappings\"",
+];
+/// `--all-values` has no types, the types of the first answer are kept, see `merge_types`. The
+/// frame address comes first, the variable objects are keyed on it (see `varobj_commands`).
+const VARIABLES_COMMANDS: &[&str] = &[
+    "-interpreter-exec console \"info frame\"",
+    "-stack-list-variables --simple-values",
+    "-stack-list-variables --all-values",
+];
+/// In async mode GDB answers right away, so the panels are refreshed once the target stops
+const REFRESH_COMMANDS: &[&str] = &[
+    "-thread-info",
+    "-stack-list-frames",
+    "-stack-list-arguments --simple-values",
+    VARIABLES_COMMANDS[0],
+    VARIABLES_COMMANDS[1],
+    VARIABLES_COMMANDS[2],
+    "-var-update --all-values *",
+    "-data-list-register-values x",
+];
 
 /// We need to keep track of the commands we sent to the shell to be able to backtrack errors and
 /// restart a shell and go back to a known state
@@ -54,9 +101,97 @@ impl BoxedFn {
 pub struct PersistentData {
     pub line: Option<u32>,
     pub file: Option<PathBuf>,
+    /// `file` is there on this machine, checked when it changes rather than on every frame
+    pub file_exists: bool,
     pub frames: Option<Vec<mi_types::Frame>>,
+    /// Value returned by the last `-exec-finish`, cleared on the next stop.
+    pub return_value: Option<String>,
+    /// Signal that stopped the inferior or, for core files, that killed it
+    pub signal: Option<mi_types::Signal>,
+    pub threads: Option<Vec<mi_types::Thread>>,
+    /// The selection as GDB last reported it, answers to `-stack-list-*` belong to it
+    pub selected_thread: Option<String>,
+    pub selected_frame: Option<u32>,
+    /// Address of the selected frame, from `info frame`
+    pub frame_address: Option<u64>,
+    /// Thread the inferior stopped (or crashed) in
+    pub stopped_thread: Option<String>,
+    /// Backtraces of every thread, keyed by thread id. Every frame list GDB sends is kept, only
+    /// the walk of `collect_commands` (core files, crash reports) fills in all the threads
+    pub thread_frames: HashMap<String, Vec<mi_types::Frame>>,
+    /// Locals of every frame, keyed by thread id and frame level. Filled in like `thread_frames`
+    pub frame_variables: HashMap<(String, u32), Vec<mi_types::Variable>>,
+    /// Locals and arguments of the selected frame
+    pub variables: Vec<mi_types::Variable>,
+    /// `variables` as they were at the previous stop, empty if it stopped in another function
+    pub previous_variables: Vec<mi_types::Variable>,
+    /// Variable objects of the selected frame's variables, see `varobj_commands`
+    pub varobjs: varobj::VarObjCache,
+    /// Expressions evaluated on every stop
+    pub watches: Vec<watch::Watch>,
+    /// Threads `collect_commands` still has to walk, and the one it's walking
+    pub walk: Vec<String>,
+    pub walk_current: Option<String>,
+    pub register_names: Vec<String>,
+    /// Register numbers and their values in hex
+    pub registers: Vec<(u32, String)>,
+    /// `registers` as they were at the previous stop
+    pub previous_registers: Vec<(u32, String)>,
+    /// Instructions around the PC of the selected frame, or wherever the user went from there
+    pub disassembly: disassembly::Disassembly,
+    /// What the memory view shows
+    pub memory: memory::Memory,
+    pub report: report::CrashReportState,
+    /// GDB said non-stop mode is on, threads stop and run on their own
+    pub non_stop: bool,
+    /// The processes GDB debugs and what it does when they fork or exec
+    pub inferiors: inferiors::Inferiors,
+    /// GDB's answer to `-complete`, until the command line takes it
+    pub completion: Option<(Option<String>, Vec<String>)>,
+    /// Expressions evaluated in the REPL panel
+    pub repl: repl::Repl,
+    /// "Step only the selected thread" is ticked
+    pub scheduler_locked: bool,
+    /// A new GDB has to be told about `scheduler_locked` at its first stop. The history it replays
+    /// may set it before there's a process, which GDB refuses.
+    pub relock_scheduler: bool,
 }
 
+impl PersistentData {
+    /// A GDB that was just started has nothing of what was set in the one before
+    pub fn gdb_restarted(&mut self) {
+        watch::forget_watchpoints(&mut self.watches);
+        self.relock_scheduler = self.scheduler_locked;
+        // Until it answers the `-gdb-show non-stop` of the launch again
+        self.non_stop = false;
+    }
+
+    /// A register changed if its value isn't the one it had at the previous stop
+    pub fn register_changed(&self, number: u32, value: &str) -> bool {
+        self.previous_registers
+            .iter()
+            .find(|(n, _)| *n == number)
+            .is_some_and(|(_, v)| v != value)
+    }
+
+    /// Stopped somewhere we have no source for: no debug information, or a path that only exists
+    /// where the binary was built
+    pub fn asm_only(&self) -> bool {
+        self.frames.is_some() && !self.file_exists
+    }
+
+    /// A variable changed if its value isn't the one it had at the previous stop
+    pub fn variable_changed(&self, var: &mi_types::Variable) -> bool {
+        self.previous_variables
+            .iter()
+            .find(|v| v.name == var.name && v.arg == var.arg)
+            .is_some_and(|v| v.value != var.value)
+    }
+}
+
+/// What a button does, it gets the state it was clicked in and the input fields
+pub type ButtonFn = fn(&ControlState, &[String]) -> ControlState;
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum ControlState {
     LookingForGDB,
@@ -66,8 +201,34 @@ pub enum ControlState {
     AttachFileDialog {
         path: Option<String>,
     },
+    /// Arguments, environment and working directory of the binary chosen in `AttachFileDialog`
+    LaunchDialog {
+        config: LaunchConfig,
+        launch: bool,
+    },
+    /// A kernel behind a gdbstub, with its symbols and the architecture to debug it as
     TryAttachPort {
-        host: Option<String>,
+        config: Option<kernel::KernelConfig>,
+    },
+    /// QEMU binary, kernel and the rest, the UI starts QEMU when `launch` is set and we connect to
+    /// its gdbstub
+    QemuDialog {
+        config: qemu::QemuConfig,
+        launch: bool,
+    },
+    /// A program under gdbserver, the UI starts a local gdbserver when `launch` is set
+    GdbserverDialog {
+        config: gdbserver::GdbserverConfig,
+        launch: bool,
+    },
+    /// Executable and core file
+    CoreFileDialog {
+        files: Option<(String, String)>,
+    },
+    /// Picks a local process from `/proc` and runs `-target-attach` on it
+    AttachProcessDialog {
+        pid: Option<u32>,
+        error: Option<String>,
     },
 
     GDBRunning {
@@ -86,7 +247,10 @@ pub enum ControlState {
         prev: Box<ControlState>,
     },
 
-    GDBExited,
+    /// The inferior is gone but GDB is still alive, `status` is `None` when we killed it
+    GDBExited {
+        status: Option<mi_types::ExitStatus>,
+    },
 }
 
 impl fmt::Debug for BoxedFn {
@@ -106,6 +270,8 @@ pub enum GDBExecutionState {
     Running,
     Stopped,
     Unknown,
+    /// Looking at a core file, there's no process to run
+    PostMortem,
 }
 
 fn execution_state_from_output(cur: &GDBExecutionState, output: &mi::Output) -> GDBExecutionState {
@@ -121,24 +287,86 @@ impl ControlState {
         ControlState::LookingForGDB
     }
 
-    pub fn buttons(&self) -> &[(&str, fn(&ControlState, &[String]) -> ControlState)] {
+    pub fn buttons(&self) -> &[(&str, ButtonFn)] {
         use ControlState::*;
         match self {
             GDBNothingLoaded => &[
                 ("Attach to port (QEMU)", |_, _| -> ControlState {
-                    ControlState::TryAttachPort { host: None }
+                    ControlState::TryAttachPort { config: None }
+                }),
+                ("Launch QEMU", |_, _| QemuDialog {
+                    config: qemu::QemuConfig::load(),
+                    launch: false,
                 }),
                 ("Load binary", |_, str_in| -> ControlState {
                     AttachFileDialog { path: None }
                 }),
+                ("Debug under gdbserver", |_, _| GdbserverDialog {
+                    config: gdbserver::GdbserverConfig::load(),
+                    launch: false,
+                }),
+                ("Attach to process", |_, _| AttachProcessDialog {
+                    pid: None,
+                    error: None,
+                }),
+                ("Load core file", |_, _| CoreFileDialog { files: None }),
+            ],
+            CoreFileDialog { files: None } => &[
+                ("Load", |_, str_in| CoreFileDialog {
+                    files: Some((str_in[0].clone(), str_in[1].clone())),
+                }),
+                ("Back", |_, _| GDBNothingLoaded),
             ],
             AttachFileDialog { path: None } => &[("Load", |_, str_in| AttachFileDialog {
                 path: Some(str_in[0].clone()),
             })],
+            LaunchDialog { launch: false, .. } => &[
+                ("Launch", |prev, str_in| match prev {
+                    LaunchDialog { config, .. } => LaunchDialog {
+                        config: LaunchConfig::from_fields(&config.path, str_in),
+                        launch: true,
+                    },
+                    _ => prev.clone(),
+                }),
+                ("Back", |_, _| AttachFileDialog { path: None }),
+            ],
 
-            TryAttachPort { host: None } => &[("Connect", |_, str_in| TryAttachPort {
-                host: Some(str_in[0].clone()),
-            })],
+            GdbserverDialog { launch: false, .. } => &[
+                ("Start", |_, str_in| GdbserverDialog {
+                    config: gdbserver::GdbserverConfig::from_fields(str_in),
+                    launch: true,
+                }),
+                ("Back", |_, _| GDBNothingLoaded),
+            ],
+            TryAttachPort { config: None } => &[
+                ("Connect", |_, str_in| TryAttachPort {
+                    config: Some(kernel::KernelConfig::from_fields(str_in)),
+                }),
+                ("Back", |_, _| GDBNothingLoaded),
+            ],
+            QemuDialog { launch: false, .. } => &[
+                ("Launch", |_, str_in| QemuDialog {
+                    config: qemu::QemuConfig::from_fields(str_in),
+                    launch: true,
+                }),
+                ("Back", |_, _| GDBNothingLoaded),
+            ],
+
+            AttachProcessDialog { pid: None, .. } => &[
+                ("Attach", |_, str_in| {
+                    match str_in[1].trim().parse::<u32>() {
+                        Ok(pid) => AttachProcessDialog {
+                            pid: Some(pid),
+                            error: None,
+                        },
+                        Err(_) => AttachProcessDialog {
+                            pid: None,
+                            error: Some(format!("{:?} is not a PID", str_in[1])),
+                        },
+                    }
+                }),
+                ("Back", |_, _| GDBNothingLoaded),
+            ],
 
             GDBRunning { .. } => &[
                 ("Reload", |prev, _| RestartAndRecover {
@@ -147,16 +375,73 @@ impl ControlState {
                 }),
                 ("Step", |_, _| {
                     ControlState::send_commands(
-                        &STEP_COMMANDS,
+                        STEP_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Next", |_, _| {
+                    ControlState::send_commands(
+                        NEXT_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Finish", |_, _| {
+                    ControlState::send_commands(
+                        FINISH_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Step instruction", |_, _| {
+                    ControlState::send_commands(
+                        STEPI_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Next instruction", |_, _| {
+                    ControlState::send_commands(
+                        NEXTI_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Until", |_, _| {
+                    ControlState::send_commands(
+                        UNTIL_COMMANDS,
                         ControlState::no_stderr(ControlState::running_default()),
                     )
                 }),
                 ("Continue", |_, _| {
                     ControlState::send_commands(
-                        &["-exec-continue"],
+                        CONTINUE_COMMANDS,
                         ControlState::no_stderr(ControlState::running_default()),
                     )
                 }),
+                ("Pause", |_, _| {
+                    ControlState::send_commands(
+                        INTERRUPT_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Kill", |_, _| {
+                    ControlState::send_commands(
+                        KILL_COMMANDS,
+                        ControlState::no_stderr(GDBExited { status: None }),
+                    )
+                }),
+                ("Restart", |_, _| {
+                    ControlState::send_commands(
+                        RESTART_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Detach", |_, _| {
+                    ControlState::send_commands(
+                        DETACH_COMMANDS,
+                        ControlState::no_stderr(GDBNothingLoaded),
+                    )
+                }),
+                ("Generate crash report", |prev, _| {
+                    ControlState::crash_report(prev)
+                }),
                 //("List frames", |_, _| {
                 //    ControlState::send_commands(
                 //        &["-stack-list-frames"],
@@ -165,10 +450,241 @@ impl ControlState {
                 //}),
             ],
 
+            GDBExited { .. } => &[
+                ("Run again", |_, _| {
+                    ControlState::send_commands(
+                        RUN_AGAIN_COMMANDS,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )
+                }),
+                ("Load another binary", |_, _| GDBNothingLoaded),
+            ],
+
             _ => &[],
         }
     }
 
+    /// The buttons, except that without source "Step" and "Next" go by instruction. There's no
+    /// line to stop at, `-exec-step` would run until it finds a function with line information.
+    /// A crash report is only offered for a core file or a process a signal stopped, there's
+    /// nothing to report otherwise.
+    pub fn buttons_for(&self, data: &PersistentData) -> Vec<(&str, ButtonFn)> {
+        let post_mortem = matches!(
+            self,
+            ControlState::GDBRunning {
+                state: GDBExecutionState::PostMortem,
+                ..
+            }
+        );
+        let buttons = self
+            .buttons()
+            .iter()
+            .filter(|(label, _)| {
+                *label != "Generate crash report" || post_mortem || data.signal.is_some()
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        if !data.asm_only() {
+            return buttons;
+        }
+
+        buttons
+            .iter()
+            .map(|(label, f)| {
+                let instead = match *label {
+                    "Step" => "Step instruction",
+                    "Next" => "Next instruction",
+                    other => other,
+                };
+                let f = buttons
+                    .iter()
+                    .find(|(l, _)| *l == instead)
+                    .map_or(*f, |(_, f)| *f);
+                (*label, f)
+            })
+            .collect()
+    }
+
+    /// While the target is running only the commands that don't need a stopped thread make sense
+    pub fn button_enabled(&self, button: &str) -> bool {
+        use ControlState::*;
+        match self {
+            GDBRunning {
+                state: GDBExecutionState::Running,
+                ..
+            } => matches!(button, "Reload" | "Continue" | "Pause" | "Kill" | "Restart"),
+            GDBRunning {
+                state: GDBExecutionState::PostMortem,
+                ..
+            } => matches!(button, "Reload" | "Generate crash report"),
+            GDBRunning { .. } => button != "Pause",
+            _ => true,
+        }
+    }
+
+    /// There's a process and it isn't running, so its registers and memory can be written and
+    /// breakpoints changed. A core file has no process, and while commands are on their way the
+    /// inferior may be starting.
+    pub fn is_stopped(&self) -> bool {
+        matches!(
+            self,
+            ControlState::GDBRunning {
+                state: GDBExecutionState::Stopped | GDBExecutionState::Unknown,
+                ..
+            }
+        )
+    }
+
+    /// Keeps running until `line` of `file` is reached or the current frame returns. `-exec-until`
+    /// inserts a software breakpoint, so with `hardware` it's a temporary hardware one instead and
+    /// returning from the frame doesn't stop.
+    pub fn run_to_line(file: &Path, line: u32, hardware: bool) -> ControlState {
+        let location = format!("\"{}:{line}\"", mi::escape(&file.display().to_string()));
+        let cmds = if hardware {
+            vec![
+                format!("-break-insert -t -h {location}"),
+                "-exec-continue".to_string(),
+            ]
+        } else {
+            vec![format!("-exec-until {location}")]
+        };
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(
+            &cmds,
+            ControlState::no_stderr(ControlState::running_default()),
+        )
+    }
+
+    /// Walks every thread like a core file does, and then asks for the registers, disassembly and
+    /// memory map. The report is ready once `PersistentData::report` says so.
+    fn crash_report(prev: &ControlState) -> ControlState {
+        ControlState::send_commands(
+            &[
+                "-thread-info",
+                &format!("{}-thread-list-ids", tokens::REPORT),
+            ],
+            ControlState::no_stderr(prev.clone()),
+        )
+    }
+
+    /// Makes `level` the selected frame, the source view, the variables and the watches follow it
+    pub fn select_frame(prev: &ControlState, level: u32, data: &PersistentData) -> ControlState {
+        let mut cmds = vec![
+            format!("-stack-select-frame {level}"),
+            "-stack-info-frame".to_string(),
+        ];
+        cmds.extend(VARIABLES_COMMANDS.to_cmds());
+        cmds.extend(watch::evaluate_commands(&data.watches));
+        cmds.push(data.disassembly.command());
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(prev.clone()))
+    }
+
+    /// Makes `id` the selected thread, the stack, the variables and the registers follow it. In
+    /// non-stop mode the buttons follow whether it's running.
+    pub fn select_thread(prev: &ControlState, id: &str, data: &PersistentData) -> ControlState {
+        let mut cmds = vec![format!("-thread-select {id}")];
+        cmds.extend(ControlState::thread_selected_commands(data));
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let running = data
+            .threads
+            .iter()
+            .flatten()
+            .any(|t| t.id == id && t.state == "running");
+        let next = match prev {
+            ControlState::GDBRunning { last_output, .. } if data.non_stop => {
+                ControlState::GDBRunning {
+                    state: if running {
+                        GDBExecutionState::Running
+                    } else {
+                        GDBExecutionState::Stopped
+                    },
+                    last_output: last_output.clone(),
+                }
+            }
+            _ => prev.clone(),
+        };
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(next))
+    }
+
+    /// Sets the setting at index `i` of `inferiors::SETTINGS`
+    pub fn set_inferior_setting(prev: &ControlState, i: usize, value: &str) -> ControlState {
+        let cmds = inferiors::set_commands(i, value);
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(prev.clone()))
+    }
+
+    pub fn switch_inferior(prev: &ControlState, id: &str) -> ControlState {
+        let cmds = inferiors::switch_commands(id);
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(prev.clone()))
+    }
+
+    /// Non-stop mode only, the other threads keep doing what they do
+    pub fn continue_thread(prev: &ControlState, id: &str) -> ControlState {
+        ControlState::send_commands(
+            &[&format!("-exec-continue --thread {id}")],
+            ControlState::no_stderr(prev.clone()),
+        )
+    }
+
+    pub fn interrupt_thread(prev: &ControlState, id: &str) -> ControlState {
+        ControlState::send_commands(
+            &[&format!("-exec-interrupt --thread {id}")],
+            ControlState::no_stderr(prev.clone()),
+        )
+    }
+
+    fn thread_selected_commands(data: &PersistentData) -> Vec<String> {
+        let mut cmds = vec![
+            "-stack-list-frames".to_string(),
+            "-stack-list-arguments --simple-values".to_string(),
+        ];
+        cmds.extend(VARIABLES_COMMANDS.to_cmds());
+        cmds.push("-data-list-register-values x".to_string());
+        cmds.extend(watch::evaluate_commands(&data.watches));
+        cmds.push(data.disassembly.command());
+        cmds
+    }
+
+    /// With the scheduler locked only the selected thread moves when stepping, the others stay
+    /// where they are
+    pub fn lock_scheduler(prev: &ControlState, locked: bool) -> ControlState {
+        ControlState::send_commands(
+            &[&scheduler_command(locked)],
+            ControlState::no_stderr(prev.clone()),
+        )
+    }
+
+    /// Assigns `value`, an expression GDB understands, to the register `name`
+    pub fn write_register(prev: &ControlState, name: &str, value: &str) -> ControlState {
+        let cmds = registers::write_commands(name, value);
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(prev.clone()))
+    }
+
+    /// Sets or deletes watchpoints, the history has them so "Reload" does it again
+    pub fn edit_watchpoints(prev: &ControlState, cmds: &[String]) -> ControlState {
+        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+        ControlState::send_commands(&cmds, ControlState::no_stderr(prev.clone()))
+    }
+
+    /// Lists the children of a variable object, they show up in `PersistentData::varobjs`
+    pub fn list_children(prev: &ControlState, name: &str) -> ControlState {
+        ControlState::send_commands(
+            &[&varobj::VarObjCache::list_children_command(name)],
+            ControlState::no_stderr(prev.clone()),
+        )
+    }
+
     fn running_default() -> ControlState {
         ControlState::GDBRunning {
             state: GDBExecutionState::Unknown,
@@ -180,12 +696,66 @@ impl ControlState {
         use ControlState::*;
         match self {
             AttachFileDialog { path: None } => &[("Filename", "./res/a.out")],
+            LaunchDialog { launch: false, .. } => &[
+                ("Arguments", ""),
+                ("Working directory", ""),
+                ("Environment (FOO=1 BAR=2)", ""),
+                ("Non-stop mode (on/off)", "off"),
+            ],
 
-            TryAttachPort { host: None } => &[("Host Address", "127.0.0.1:1234")],
+            GdbserverDialog { launch: false, .. } => &[
+                ("Mode (local/remote)", "local"),
+                ("gdbserver address", ":2345"),
+                ("Program", "./res/a.out"),
+                ("Arguments", ""),
+                ("Path on the target (remote put)", ""),
+                ("Sysroot", ""),
+                ("Fetch from the target (remote=local ...)", ""),
+            ],
+            TryAttachPort { config: None } => &[
+                ("Host Address", "127.0.0.1:1234"),
+                ("Symbol file (vmlinux)", ""),
+                ("KASLR offset", ""),
+                ("Modules (path@address ...)", ""),
+                ("Architecture", "auto"),
+                ("Hardware breakpoints (on/off)", "on"),
+            ],
+            QemuDialog { launch: false, .. } => &[
+                ("QEMU binary", "qemu-system-x86_64"),
+                ("Kernel image", ""),
+                ("initrd", ""),
+                ("Extra arguments", ""),
+                ("GDB port", "1234"),
+            ],
+            AttachProcessDialog { pid: None, .. } => &[("Filter", ""), ("PID", "")],
+            CoreFileDialog { files: None } => {
+                &[("Executable", "./res/a.out"), ("Core file", "./core")]
+            }
             _ => &[],
         }
     }
 
+    /// Values the input fields start with when we enter this state
+    pub fn input_values(&self) -> Vec<String> {
+        use ControlState::*;
+        match self {
+            LaunchDialog {
+                config,
+                launch: false,
+            } => config.to_fields(),
+            QemuDialog {
+                config,
+                launch: false,
+            } => config.to_fields(),
+            GdbserverDialog {
+                config,
+                launch: false,
+            } => config.to_fields(),
+            TryAttachPort { config: None } => kernel::KernelConfig::load().to_fields(),
+            _ => vec![],
+        }
+    }
+
     // @TODO: handle GDB errors here from the STDOUT... maybe not HERE, but somewhere else
     fn no_stderr(next: ControlState) -> impl Fn(ControlState, ConsoleOutput) -> ControlState {
         move |state, input| match input {
@@ -194,6 +764,28 @@ impl ControlState {
         }
     }
 
+    /// Waits for the result record of the command sent with `token`. Answers to the commands sent
+    /// before it may still be on their way, they are ignored with everything else.
+    fn expect_result(
+        token: u64,
+        next: ControlState,
+        on_error: fn(String) -> ControlState,
+    ) -> impl Fn(ControlState, ConsoleOutput) -> ControlState {
+        move |state, input| match input {
+            ConsoleOutput::Stdout(s) if mi::token(&s) == Some(token) => {
+                match mi::parse_stream(&s) {
+                    Ok((_, Output::ResultRecord(mi_types::MIResult::Error { msg, .. }, _))) => {
+                        on_error(msg)
+                    }
+                    Ok((_, Output::ResultRecord(..))) => next.clone(),
+                    _ => state,
+                }
+            }
+            ConsoleOutput::Stdout(_) => state,
+            ConsoleOutput::Stderr(e) => panic!("{}", e),
+        }
+    }
+
     fn send_commands(
         cmds: &[&str],
         check: impl Fn(ControlState, ConsoleOutput) -> ControlState + Sync + Send + 'static,
@@ -206,12 +798,29 @@ impl ControlState {
     }
 }
 
+/// A command sent outside of `SendCommand` that a GDB started by "Reload" needs as well
+pub fn remember(cmd: &str) {
+    CMD_HISTORY.write().push(cmd.to_string());
+}
+
 // (Kernel debugging) Attach to a running QEMU instance.
 // (Userspace debugging) Run an executable file.
 fn try_run() -> Option<()> {
     None
 }
 
+fn scheduler_command(locked: bool) -> String {
+    let mode = if locked { "step" } else { "off" };
+    format!("-gdb-set scheduler-locking {mode}")
+}
+
+/// A new GDB, which goes through everything the old one was sent
+fn recover_commands(history: &[String]) -> Vec<String> {
+    let mut cmds = vec!["quit".to_string(), "pwd".to_string()];
+    cmds.extend(history.iter().cloned());
+    cmds
+}
+
 pub fn advance_cmds(state: &ControlState) -> (ControlState, Vec<String>) {
     use ControlState::*;
 
@@ -232,26 +841,145 @@ pub fn advance_cmds(state: &ControlState) -> (ControlState, Vec<String>) {
         ),
 
         AttachFileDialog { path: Some(p) } => (
+            LaunchDialog {
+                config: LaunchConfig::load(p),
+                launch: false,
+            },
+            vec![],
+        ),
+
+        LaunchDialog {
+            config,
+            launch: true,
+        } => {
+            if let Err(e) = config.save() {
+                println!("Failed to save the launch configuration: {e}");
+            }
+
+            let mut cmds = config.commands();
+            cmds.push("start".to_string());
+            let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+            (
+                ControlState::send_commands(
+                    START_COMMANDS,
+                    ControlState::no_stderr(ControlState::send_commands(
+                        &cmds,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )),
+                ),
+                vec![],
+            )
+        }
+
+        TryAttachPort {
+            config: Some(config),
+        } => {
+            if let Err(e) = config.save() {
+                println!("Failed to save the kernel configuration: {e}");
+            }
+
+            let cmds = config.commands();
+            let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+            (
+                ControlState::send_commands(
+                    START_COMMANDS,
+                    ControlState::no_stderr(ControlState::send_commands(
+                        &cmds,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )),
+                ),
+                vec![],
+            )
+        }
+
+        // QEMU was started with `-S`, it waits for us before running the kernel
+        QemuDialog {
+            config,
+            launch: true,
+        } => {
+            if let Err(e) = config.save() {
+                println!("Failed to save the QEMU configuration: {e}");
+            }
+
+            let cmds = config.kernel_config().commands();
+            let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+            (
+                ControlState::send_commands(
+                    START_COMMANDS,
+                    ControlState::no_stderr(ControlState::send_commands(
+                        &cmds,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )),
+                ),
+                vec![],
+            )
+        }
+
+        GdbserverDialog {
+            config,
+            launch: true,
+        } => {
+            if let Err(e) = config.save() {
+                println!("Failed to save the gdbserver configuration: {e}");
+            }
+
+            let cmds = config.commands();
+            let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+
+            (
+                ControlState::send_commands(
+                    START_COMMANDS,
+                    ControlState::no_stderr(ControlState::send_commands(
+                        &cmds,
+                        ControlState::no_stderr(ControlState::running_default()),
+                    )),
+                ),
+                vec![],
+            )
+        }
+
+        CoreFileDialog {
+            files: Some((exe, core)),
+        } => (
             ControlState::send_commands(
-                &[&format!("file {p}"), "start"],
+                START_COMMANDS,
                 ControlState::no_stderr(ControlState::send_commands(
-                    START_COMMANDS,
-                    ControlState::no_stderr(ControlState::running_default()),
+                    &[
+                        &format!("file \"{}\"", mi::escape(exe)),
+                        &format!("target core \"{}\"", mi::escape(core)),
+                        COLLECT_COMMANDS[0],
+                        COLLECT_COMMANDS[1],
+                    ],
+                    ControlState::no_stderr(GDBRunning {
+                        state: GDBExecutionState::PostMortem,
+                        last_output: None,
+                    }),
                 )),
             ),
             vec![],
         ),
 
-        TryAttachPort { host: Some(h) } => (
+        AttachProcessDialog { pid: Some(pid), .. } => (
             ControlState::send_commands(
-                &[&format!("target remote {h}")],
+                START_COMMANDS,
                 ControlState::no_stderr(ControlState::send_commands(
-                    START_COMMANDS,
-                    ControlState::no_stderr(ControlState::running_default()),
+                    &[&format!("{}-target-attach {pid}", tokens::ATTACH)],
+                    ControlState::expect_result(
+                        tokens::ATTACH,
+                        ControlState::running_default(),
+                        |msg| AttachProcessDialog {
+                            pid: None,
+                            error: Some(procs::describe_attach_error(&msg)),
+                        },
+                    ),
                 )),
             ),
             vec![],
         ),
+
         SendCommand {
             commands: cmds,
             check: f,
@@ -273,16 +1001,9 @@ pub fn advance_cmds(state: &ControlState) -> (ControlState, Vec<String>) {
             )
         }
 
-        RestartAndRecover { sent: false, prev } => unsafe {
-            let mut cmds = vec!["quit".to_string(), "pwd".to_string()];
-            let len = CMD_HISTORY.read().len();
-            for cmd in &CMD_HISTORY.read()[0..len] {
-                cmds.push(cmd.clone());
-            }
-
-            (*(*prev).clone(), cmds)
-        },
-        GDBExited => (GDBNothingLoaded, vec![]),
+        RestartAndRecover { sent: false, prev } => {
+            (*(*prev).clone(), recover_commands(&CMD_HISTORY.read()))
+        }
         _ => (state.clone(), vec![]),
     }
 }
@@ -295,6 +1016,16 @@ pub fn read_console_input(
     use ConsoleOutput::*;
     use ControlState::*;
 
+    // Tab completion works in every state, and its answer isn't the one a command waits for
+    if let Stdout(line) = input {
+        if mi::token(line) == Some(tokens::COMPLETE) {
+            if let Ok((_, output)) = mi::parse_stream(line) {
+                data.completion = query::completion(&output);
+            }
+            return state;
+        }
+    }
+
     match state {
         SendCommand {
             check: BoxedFn(ref verify),
@@ -302,31 +1033,126 @@ pub fn read_console_input(
             ..
         } => {
             let next = verify(state.clone(), input.clone());
+
+            // The check is still waiting for its answer
+            if next == state {
+                return next;
+            }
+
             read_console_input(next, data, input)
         }
 
+        // Answers to what was sent before still come until `advance_cmds` sends these, their
+        // follow ups go out with them
+        SendCommand {
+            commands,
+            check,
+            sent: false,
+        } => {
+            let follow_up = match input {
+                Stdout(line) => mi::parse_stream(line)
+                    .ok()
+                    .and_then(|(_, output)| process_output(line, &output, data)),
+                Stderr(_) => None,
+            };
+
+            SendCommand {
+                commands: commands
+                    .into_iter()
+                    .chain(follow_up.into_iter().flatten())
+                    .collect(),
+                check,
+                sent: false,
+            }
+        }
+
         GDBRunning { state, last_output } => match input {
-            Stdout(input) => {
-                if let Ok((_, ref output)) = mi::parse_stream(input) {
-                    *data = update_persistent_data(&output, &data);
-                    let next_state = execution_state_from_output(&state, output);
+            Stdout(line) => {
+                let output = match mi::parse_stream(line) {
+                    Ok((_, output)) => output,
+                    Err(_) => return GDBRunning { state, last_output },
+                };
+
+                if other_thread_event(&output, data) {
+                    if let Some(ids) = query::stopped_threads(&output) {
+                        mark_threads(data, &ids, "stopped");
+                    }
+                    if let Some(id) = query::running_thread(&output) {
+                        mark_threads(data, &[id], "running");
+                    }
+
+                    return ControlState::send_commands(
+                        &["-thread-info"],
+                        ControlState::no_stderr(GDBRunning { state, last_output }),
+                    );
+                }
 
-                    let exited = query::has_exited(output);
+                let follow_up = process_output(line, &output, data);
+                let next_state = execution_state_from_output(&state, &output);
 
-                    if exited {
-                        return GDBExited;
+                if let Some(status) = query::exit_status(&output) {
+                    return GDBExited {
+                        status: Some(status),
+                    };
+                }
+
+                let next = GDBRunning {
+                    state: match (state, next_state) {
+                        (GDBExecutionState::PostMortem, _) => state,
+                        (_, GDBExecutionState::Unknown) => state,
+                        _ => next_state,
+                    },
+                    last_output: Some(output.clone()),
+                };
+
+                if next_state == GDBExecutionState::Stopped {
+                    let mut cmds = REFRESH_COMMANDS.to_cmds();
+                    // GDB may still have the thread we were looking at selected
+                    if let (true, Some(t)) = (data.non_stop, &data.stopped_thread) {
+                        cmds.insert(0, format!("-thread-select {t}"));
+                    }
+                    // The names don't change, they are only asked for once
+                    if data.register_names.is_empty() {
+                        cmds.push("-data-list-register-names".to_string());
                     }
+                    cmds.extend(watch::evaluate_commands(&data.watches));
+                    cmds.extend(data.memory.refresh_command());
+                    cmds.push(data.disassembly.command());
+                    if std::mem::take(&mut data.relock_scheduler) {
+                        cmds.push(scheduler_command(true));
+                    }
+                    let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
 
-                    GDBRunning {
-                        state: if next_state != GDBExecutionState::Unknown {
-                            next_state
-                        } else {
-                            state
-                        },
-                        last_output: Some(output.clone()),
+                    return ControlState::send_commands(&cmds, ControlState::no_stderr(next));
+                }
+
+                match follow_up {
+                    Some(cmds) => {
+                        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+                        ControlState::send_commands(&cmds, ControlState::no_stderr(next))
                     }
-                } else {
-                    GDBRunning { state, last_output }
+                    None => next,
+                }
+            }
+            Stderr(e) => panic!("{}", e),
+        },
+
+        // GDB still answers the crash report commands after the inferior is gone
+        GDBExited { status } => match input {
+            Stdout(line) => {
+                let follow_up = mi::parse_stream(line)
+                    .ok()
+                    .and_then(|(_, output)| process_output(line, &output, data));
+
+                match follow_up {
+                    Some(cmds) => {
+                        let cmds = cmds.iter().map(String::as_str).collect::<Vec<_>>();
+                        ControlState::send_commands(
+                            &cmds,
+                            ControlState::no_stderr(GDBExited { status }),
+                        )
+                    }
+                    None => GDBExited { status },
                 }
             }
             Stderr(e) => panic!("{}", e),
@@ -337,13 +1163,981 @@ pub fn read_console_input(
 }
 
 fn update_persistent_data(output: &Output, persistent_data: &PersistentData) -> PersistentData {
-    match persistent_data {
-        PersistentData { line, file, frames } => PersistentData {
-            line: query::current_line(output).or(line.clone()),
-            file: query::current_file(output).or(file.clone()),
-            frames: query::frames(output).or(frames.clone()),
-            // @TODO: Fill the frame fields with another query
-            //frames: query::frames(output).or(frames.clone()).and_then(..),
-        },
+    let mut data = persistent_data.clone();
+
+    // Every record with a frame says where we are, one without source mustn't keep the last file
+    if query::frame(output).is_some() {
+        data.line = query::current_line(output);
+        let file = query::current_file(output);
+        if file != data.file {
+            data.file_exists = file.as_ref().is_some_and(|f| f.exists());
+            data.file = file;
+        }
+    }
+    data.frames = query::frames(output).or(data.frames);
+    // @TODO: Fill the frame fields with another query
+    //frames: query::frames(output).or(frames.clone()).and_then(..),
+    if query::has_stopped(output) {
+        data.return_value = query::return_value(output);
+        data.previous_registers = data.registers.clone();
+        data.memory.stopped();
+        data.disassembly.at = None;
+
+        // The frame list still belongs to the previous stop
+        let previous = data.frames.as_ref().and_then(|f| f.first());
+        data.previous_variables = match (query::frame(output), previous) {
+            (Some(now), Some(before)) if now.func == before.func => data.variables.clone(),
+            _ => vec![],
+        };
+    }
+
+    if let Some(vars) = query::variables(output) {
+        data.variables = merge_types(vars, &data.variables);
+    }
+
+    // A core file's `*stopped` has no reason, it mustn't clear the signal we got from the console
+    data.signal = match (query::signal(output), query::stop_reason(output)) {
+        (Some(signal), _) => Some(signal),
+        (None, Some(_)) => None,
+        (None, None) => data.signal,
+    };
+
+    data.threads = query::threads(output).or(data.threads);
+    // Nothing else says so until the next `-thread-info`
+    if let Some(id) = query::running_thread(output) {
+        mark_threads(&mut data, &[id], "running");
+    }
+    if let Some(ids) = query::stopped_threads(output) {
+        mark_threads(&mut data, &ids, "stopped");
+    }
+    data.selected_thread = query::selected_thread(output).or(data.selected_thread);
+    data.selected_frame = query::selected_frame(output).or(data.selected_frame);
+    data.frame_address = query::frame_address(output).or(data.frame_address);
+
+    if let Some(t) = query::stopped_thread(output) {
+        data.stopped_thread = Some(t);
+    }
+
+    if let Some(thread) = &data.selected_thread {
+        if let Some(frames) = query::frames(output) {
+            data.thread_frames.insert(thread.clone(), frames);
+        }
+
+        if let (Some(level), Some(_)) = (data.selected_frame, query::variables(output)) {
+            data.frame_variables
+                .insert((thread.clone(), level), data.variables.clone());
+        }
+
+        if let (Some(frames), Some(args)) = (
+            data.thread_frames.get_mut(thread),
+            query::frame_args(output),
+        ) {
+            fill_args(frames, args);
+        }
+    }
+
+    // While walking the threads the frame list belongs to whichever thread is being walked
+    if let (None, Some(frames), Some(args)) = (
+        &data.walk_current,
+        &mut data.frames,
+        query::frame_args(output),
+    ) {
+        fill_args(frames, args);
+    }
+
+    data.register_names = query::register_names(output).unwrap_or(data.register_names);
+    data.registers = query::register_values(output).unwrap_or(data.registers);
+    if let Some(instructions) = query::instructions(output) {
+        data.disassembly.answered(instructions);
+    }
+
+    data
+}
+
+fn fill_args(frames: &mut [mi_types::Frame], args: query::FrameArgs) {
+    for (level, args) in args {
+        if let Some(f) = frames.iter_mut().find(|f| f.level == level) {
+            f.args = Some(args);
+        }
+    }
+}
+
+/// Keeps the types we got from `--simple-values` when the same variables come again without them
+fn merge_types(
+    mut vars: Vec<mi_types::Variable>,
+    known: &[mi_types::Variable],
+) -> Vec<mi_types::Variable> {
+    for v in vars.iter_mut().filter(|v| v.var_type.is_none()) {
+        v.var_type = known
+            .iter()
+            .find(|k| k.name == v.name && k.arg == v.arg)
+            .and_then(|k| k.var_type.clone());
+    }
+    vars
+}
+
+/// Updates the data with a line from GDB and returns the commands it calls for, if any
+fn process_output(line: &str, output: &Output, data: &mut PersistentData) -> Option<Vec<String>> {
+    *data = update_persistent_data(output, data);
+
+    if mi::token(line) == Some(tokens::REPORT) {
+        data.report = report::CrashReportState {
+            requested: true,
+            ..Default::default()
+        };
+    }
+
+    if let (Some(token), Some(answer)) = (mi::token(line), query::evaluation(output)) {
+        if token == tokens::NON_STOP {
+            data.non_stop = answer == Ok("on".to_string());
+        }
+        if let Ok(value) = &answer {
+            data.inferiors.setting_answered(token, value.clone());
+        }
+        watch::answered(&mut data.watches, token, answer);
+    }
+    if let Some(number) = query::breakpoint_deleted(output).or(query::watchpoint_scope(output)) {
+        watch::watchpoint_deleted(&mut data.watches, &number);
+    }
+
+    report_progress(output, data);
+    repl_answers(line, output, data);
+
+    let mut cmds = collect_commands(output, data).unwrap_or_default();
+    cmds.extend(varobj_commands(output, data));
+    cmds.extend(memory_commands(line, output, data));
+    cmds.extend(disassembly_commands(line, output, data));
+    cmds.extend(thread_commands(output, data));
+    cmds.extend(inferior_commands(output, data));
+
+    if cmds.is_empty() {
+        None
+    } else {
+        Some(cmds)
+    }
+}
+
+/// Keeps the variable objects in line with the variables of the selected frame
+fn varobj_commands(output: &Output, data: &mut PersistentData) -> Vec<String> {
+    if let Some(obj) = query::varobj(output) {
+        data.varobjs.created(obj);
+    }
+
+    if let Some(children) = query::varobj_children(output) {
+        data.varobjs.add_children(children);
+    }
+
+    if let Some(changes) = query::varobj_changes(output) {
+        return data.varobjs.update(changes);
+    }
+
+    // The walk goes through frames that aren't the selected one
+    if data.walk_current.is_some() || query::variables(output).is_none() {
+        return vec![];
+    }
+
+    let frame = match (&data.selected_thread, data.selected_frame, &data.frames) {
+        (Some(thread), Some(level), Some(frames)) => frames
+            .iter()
+            .find(|f| f.level == level)
+            .zip(data.frame_address)
+            .map(|(f, addr)| (thread.clone(), addr, f.func.clone())),
+        _ => None,
+    };
+
+    data.varobjs.sync(frame, &data.variables)
+}
+
+/// Fills the REPL entries in, `whatis` prints the type in the console text before its result
+fn repl_answers(line: &str, output: &Output, data: &mut PersistentData) {
+    let token = mi::token(line);
+
+    if repl::is_value_token(token) {
+        if let (Some(t), Some(answer)) = (token, query::evaluation(output)) {
+            data.repl.value_answered(t, answer);
+        }
+    }
+
+    match output {
+        Output::ConsoleStream(s) => data.repl.console_output(s),
+        Output::ResultRecord(..) => data.repl.type_answered(token),
+        _ => {}
+    }
+}
+
+/// Puts the bytes in the memory view, and reads them again when GDB says they were written
+fn memory_commands(line: &str, output: &Output, data: &mut PersistentData) -> Option<String> {
+    if memory::is_memory_token(mi::token(line)) {
+        match (query::memory(output), query::evaluation(output)) {
+            (Some(bytes), _) => data.memory.answered(Ok(bytes)),
+            (None, Some(Err(e))) => data.memory.answered(Err(e)),
+            _ => {}
+        }
+    }
+
+    match query::memory_changed(output) {
+        Some((addr, len)) if data.memory.overlaps(addr, len) => data.memory.refresh_command(),
+        _ => None,
+    }
+}
+
+/// `ids` are thread ids or `all`
+fn mark_threads(data: &mut PersistentData, ids: &[String], state: &str) {
+    let threads = match &mut data.threads {
+        Some(threads) => threads,
+        None => return,
+    };
+
+    for t in threads
+        .iter_mut()
+        .filter(|t| ids.iter().any(|id| id == "all" || *id == t.id))
+    {
+        if t.state != state {
+            t.state = state.to_string();
+            t.frame = None;
+        }
+    }
+}
+
+/// In non-stop mode another thread running, or stopping while the selected one is stopped too,
+/// says nothing about the selected thread. Only the thread list has to know.
+fn other_thread_event(output: &Output, data: &PersistentData) -> bool {
+    let selected = match (&data.selected_thread, data.non_stop) {
+        (Some(selected), true) => selected,
+        _ => return false,
+    };
+    let selected_stopped = data
+        .threads
+        .iter()
+        .flatten()
+        .any(|t| t.id == *selected && t.state == "stopped");
+
+    match (query::running_thread(output), query::stopped_thread(output)) {
+        (Some(id), _) => id != "all" && id != *selected,
+        (_, Some(id)) if query::has_stopped(output) => id != *selected && selected_stopped,
+        _ => false,
+    }
+}
+
+/// Threads come and go while the inferior runs, and `thread N` in the console selects another one
+fn thread_commands(output: &Output, data: &PersistentData) -> Vec<String> {
+    if query::threads_changed(output) {
+        return vec!["-thread-info".to_string()];
+    }
+
+    match output {
+        Output::NotifyAsync(kind, _) if kind == "thread-selected" => {
+            ControlState::thread_selected_commands(data)
+        }
+        _ => vec![],
+    }
+}
+
+fn inferior_commands(output: &Output, data: &mut PersistentData) -> Vec<String> {
+    if let Some(groups) = query::thread_groups(output) {
+        data.inferiors.listed(groups);
+    }
+
+    match query::thread_group_event(output) {
+        Some(info) => data.inferiors.notified(info),
+        None => vec![],
+    }
+}
+
+/// Keeps the breakpoints the disassembly marks, and falls back to a range when there's no function
+/// to disassemble
+fn disassembly_commands(line: &str, output: &Output, data: &mut PersistentData) -> Option<String> {
+    if let Some((number, addr)) = query::breakpoint(output) {
+        data.disassembly.breakpoint_set(number, addr);
+    }
+
+    if let Some(number) = query::breakpoint_deleted(output) {
+        data.disassembly.breakpoint_deleted(&number);
+    }
+
+    match query::evaluation(output) {
+        Some(Err(e)) => data.disassembly.failed(mi::token(line), e),
+        _ => None,
+    }
+}
+
+/// Every command of the report batch has a result record, the console text in between is the
+/// memory map
+fn report_progress(output: &Output, data: &mut PersistentData) {
+    if data.report.pending == 0 {
+        return;
+    }
+
+    match output {
+        Output::ConsoleStream(s) => data.report.memory_map.push_str(s),
+        Output::ResultRecord(..) => {
+            data.report.pending -= 1;
+            data.report.ready = data.report.pending == 0;
+        }
+        _ => {}
+    }
+}
+
+/// Walks every thread and then every frame of it, so we get the backtraces, arguments and locals
+/// of the whole process. GDB answers in order, so we know which thread and frame an answer belongs
+/// to by looking at the selection it reported right before.
+/// One thread at a time: its frames are listed, and the answer with their arguments tells which
+/// frames to visit before moving on to the next thread.
+fn collect_commands(output: &Output, data: &mut PersistentData) -> Option<Vec<String>> {
+    let mut cmds = vec![];
+
+    if let Some(ids) = query::thread_ids(output) {
+        data.walk = ids;
+        data.thread_frames.clear();
+        data.frame_variables.clear();
+    } else {
+        data.walk_current.as_ref()?;
+        for (level, _) in query::frame_args(output)? {
+            cmds.push(format!("-stack-select-frame {level}"));
+            cmds.push("-stack-info-frame".to_string());
+            cmds.push("-stack-list-variables --all-values".to_string());
+        }
+    }
+    cmds.extend(next_thread_commands(data));
+
+    // Every answer from here on belongs to the report
+    if data.walk_current.is_none() && data.report.requested {
+        cmds.extend(REPORT_COMMANDS.to_cmds());
+        data.report.requested = false;
+        data.report.pending = cmds.len();
+        data.report.memory_map.clear();
+    }
+
+    Some(cmds)
+}
+
+fn next_thread_commands(data: &mut PersistentData) -> Vec<String> {
+    if data.walk.is_empty() {
+        data.walk_current = None;
+
+        // Go back to where the inferior stopped, so the source and stack views show it
+        let mut cmds = vec![];
+        if let Some(stopped) = &data.stopped_thread {
+            cmds.push(format!("-thread-select {stopped}"));
+            data.frames = data
+                .thread_frames
+                .get(stopped)
+                .cloned()
+                .or(data.frames.take());
+        }
+        cmds.push("-stack-select-frame 0".to_string());
+        cmds.push("-stack-info-frame".to_string());
+        cmds.extend(VARIABLES_COMMANDS.to_cmds());
+        cmds.extend(watch::evaluate_commands(&data.watches));
+        if data.register_names.is_empty() {
+            cmds.push("-data-list-register-names".to_string());
+        }
+        cmds.push("-data-list-register-values x".to_string());
+        cmds.extend(data.memory.refresh_command());
+        cmds.push(data.disassembly.command());
+
+        return cmds;
+    }
+
+    let thread = data.walk.remove(0);
+    let cmds = vec![
+        format!("-thread-select {thread}"),
+        "-stack-list-frames".to_string(),
+        "-stack-list-arguments --simple-values".to_string(),
+    ];
+    data.walk_current = Some(thread);
+
+    cmds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(data: &mut PersistentData, line: &str) -> Option<Vec<String>> {
+        let (_, output) = mi::parse_stream(line).unwrap();
+        process_output(line, &output, data)
+    }
+
+    #[test]
+    fn test_collect_core() {
+        let mut data = PersistentData {
+            stopped_thread: Some("1".to_string()),
+            ..Default::default()
+        };
+
+        feed(
+            &mut data,
+            r#"^done,threads=[{id="1",target-id="LWP 10",frame={level="0",addr="0x0000000000401136",func="main",args=[]},state="stopped"},{id="2",target-id="LWP 11",frame={level="0",addr="0x0000000000401150",func="worker",args=[]},state="stopped"}],current-thread-id="1""#,
+        );
+        let cmds = feed(&mut data, r#"^done,thread-ids={thread-id="1",thread-id="2"},current-thread-id="1",number-of-threads="2""#).unwrap();
+        assert_eq!(
+            cmds,
+            vec![
+                "-thread-select 1",
+                "-stack-list-frames",
+                "-stack-list-arguments --simple-values"
+            ]
+        );
+
+        feed(
+            &mut data,
+            r#"^done,new-thread-id="1",frame={level="0",addr="0x0000000000401136",func="main",args=[]}"#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="main"}]"#,
+        );
+        let cmds = feed(&mut data, r#"^done,stack-args=[frame={level="0",args=[]}]"#).unwrap();
+        assert_eq!(
+            cmds,
+            vec![
+                "-stack-select-frame 0",
+                "-stack-info-frame",
+                "-stack-list-variables --all-values",
+                "-thread-select 2",
+                "-stack-list-frames",
+                "-stack-list-arguments --simple-values"
+            ]
+        );
+
+        feed(
+            &mut data,
+            r#"^done,new-thread-id="2",frame={level="0",addr="0x0000000000401150",func="worker",args=[]}"#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401150",func="worker"},frame={level="1",addr="0x0000000000401190",func="start_thread"}]"#,
+        );
+        let cmds = feed(
+            &mut data,
+            r#"^done,stack-args=[frame={level="0",args=[{name="n",type="int",value="3"}]},frame={level="1",args=[{name="arg",type="void *",value="0x0"}]}]"#,
+        )
+        .unwrap();
+        assert_eq!(cmds.len(), 15);
+        assert_eq!(cmds[6], "-thread-select 1");
+        assert_eq!(data.thread_frames["2"].len(), 2);
+        assert_eq!(
+            data.thread_frames["2"][1].args,
+            Some(vec![("arg".into(), "void *".into(), "0x0".into())])
+        );
+        assert_eq!(data.walk_current, None);
+
+        // Answers outside of a walk don't start another one
+        let again = feed(&mut data, r#"^done,stack-args=[frame={level="0",args=[]}]"#);
+        assert_eq!(again, None);
+    }
+
+    #[test]
+    fn test_collect_report() {
+        let mut data = PersistentData::default();
+
+        let cmds = feed(
+            &mut data,
+            r#"1^done,thread-ids={thread-id="1"},current-thread-id="1",number-of-threads="1""#,
+        )
+        .unwrap();
+        assert_eq!(cmds[0], "-thread-select 1");
+        assert!(data.report.requested);
+
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="main"}]"#,
+        );
+        let cmds = feed(&mut data, r#"^done,stack-args=[frame={level="0",args=[]}]"#).unwrap();
+        assert_eq!(cmds.last().unwrap(), REPORT_COMMANDS.last().unwrap());
+        assert_eq!(data.report.pending, cmds.len());
+
+        for _ in 0..cmds.len() - 1 {
+            feed(&mut data, "^done");
+        }
+        feed(&mut data, r#"~"0x400000 0x401000 0x1000 0x0 /tmp/a.out\n""#);
+        assert!(!data.report.ready);
+
+        feed(&mut data, "^done");
+        assert!(data.report.ready);
+        assert_eq!(
+            data.report.memory_map,
+            "0x400000 0x401000 0x1000 0x0 /tmp/a.out\n"
+        );
+    }
+
+    #[test]
+    fn test_variables_changed() {
+        let mut data = PersistentData::default();
+
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x0000000000401136",func="fib",args=[],file="example.c",line="8"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="fib"}]"#,
+        );
+        feed(
+            &mut data,
+            r#"^done,variables=[{name="a",arg="1",type="int",value="1"},{name="p",type="struct point"}]"#,
+        );
+        feed(
+            &mut data,
+            r#"^done,variables=[{name="a",arg="1",value="1"},{name="p",value="{x = 1, y = 2}"}]"#,
+        );
+        assert_eq!(data.variables[1].var_type, Some("struct point".to_string()));
+        assert_eq!(data.variables[1].value, Some("{x = 1, y = 2}".to_string()));
+
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x000000000040113a",func="fib",args=[],file="example.c",line="9"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,variables=[{name="a",arg="1",value="1"},{name="p",value="{x = 3, y = 2}"}]"#,
+        );
+        assert!(!data.variable_changed(&data.variables[0]));
+        assert!(data.variable_changed(&data.variables[1]));
+        assert_eq!(data.variables[0].var_type, Some("int".to_string()));
+
+        // Nothing is compared across functions
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x000000000040113a",func="fib"}]"#,
+        );
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x0000000000401160",func="main",args=[],file="example.c",line="20"},thread-id="1",stopped-threads="all""#,
+        );
+        assert!(data.previous_variables.is_empty());
+    }
+
+    #[test]
+    fn test_varobjs_recursion() {
+        let mut data = PersistentData::default();
+
+        feed(
+            &mut data,
+            r#"*stopped,reason="breakpoint-hit",frame={addr="0x0000000000401136",func="fib",args=[],file="example.c",line="8"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="fib"},frame={level="1",addr="0x000000000040119a",func="main"}]"#,
+        );
+        feed(&mut data, r#"~"Stack level 0, frame at 0x7fffffffe0f0:\n""#);
+        let cmds = feed(
+            &mut data,
+            r#"^done,variables=[{name="n",arg="1",value="3"}]"#,
+        );
+        assert_eq!(cmds, Some(vec!["-var-create v1 * n".to_string()]));
+
+        // Stepped into `fib` again: level 0, same function and variables, but another frame
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x0000000000401136",func="fib",args=[],file="example.c",line="8"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="fib"},frame={level="1",addr="0x0000000000401167",func="fib"},frame={level="2",addr="0x000000000040119a",func="main"}]"#,
+        );
+        feed(&mut data, r#"~"Stack level 0, frame at 0x7fffffffe0d0:\n""#);
+        let cmds = feed(
+            &mut data,
+            r#"^done,variables=[{name="n",arg="1",value="2"}]"#,
+        );
+        assert_eq!(
+            cmds,
+            Some(vec![
+                "-var-delete v1".to_string(),
+                "-var-create v2 * n".to_string()
+            ])
+        );
+
+        // Same frame at the next stop, the objects are kept
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x000000000040113a",func="fib",args=[],file="example.c",line="9"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x000000000040113a",func="fib"},frame={level="1",addr="0x0000000000401167",func="fib"},frame={level="2",addr="0x000000000040119a",func="main"}]"#,
+        );
+        feed(&mut data, r#"~"Stack level 0, frame at 0x7fffffffe0d0:\n""#);
+        let cmds = feed(
+            &mut data,
+            r#"^done,variables=[{name="n",arg="1",value="2"}]"#,
+        );
+        assert_eq!(cmds, None);
+
+        // Back in `main` and into `fib` through `g`: the same depth and function, another frame
+        feed(
+            &mut data,
+            r#"*stopped,reason="breakpoint-hit",frame={addr="0x0000000000401136",func="fib",args=[],file="example.c",line="8"},thread-id="1",stopped-threads="all""#,
+        );
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="fib"},frame={level="1",addr="0x0000000000401187",func="g"},frame={level="2",addr="0x00000000004011aa",func="main"}]"#,
+        );
+        feed(&mut data, r#"~"Stack level 0, frame at 0x7fffffffe0b0:\n""#);
+        let cmds = feed(
+            &mut data,
+            r#"^done,variables=[{name="n",arg="1",value="2"}]"#,
+        );
+        assert_eq!(
+            cmds,
+            Some(vec![
+                "-var-delete v2".to_string(),
+                "-var-create v3 * n".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_threads() {
+        let mut data = PersistentData::default();
+
+        feed(
+            &mut data,
+            r#"^done,threads=[{id="1",target-id="LWP 10",name="main",frame={level="0",addr="0x0000000000401136",func="main",args=[]},state="stopped"},{id="2",target-id="LWP 11",name="worker",frame={level="0",addr="0x0000000000401150",func="worker",args=[]},state="stopped"}],current-thread-id="1""#,
+        );
+        assert_eq!(data.selected_thread.as_deref(), Some("1"));
+
+        feed(&mut data, r#"*running,thread-id="2""#);
+        let threads = data.threads.as_ref().unwrap();
+        assert_eq!(threads[0].state, "stopped");
+        assert_eq!(threads[1].state, "running");
+        assert!(threads[1].frame.is_none());
+
+        let cmds = feed(&mut data, r#"=thread-created,id="3",group-id="i1""#).unwrap();
+        assert_eq!(cmds, vec!["-thread-info"]);
+
+        // Selected from the console, the panels have to follow
+        let cmds = feed(
+            &mut data,
+            r#"=thread-selected,id="2",frame={level="0",addr="0x0000000000401150",func="worker",args=[]}"#,
+        )
+        .unwrap();
+        assert_eq!(data.selected_thread.as_deref(), Some("2"));
+        assert_eq!(cmds[0], "-stack-list-frames");
+    }
+
+    #[test]
+    fn test_non_stop() {
+        let mut data = PersistentData::default();
+        let stopped = ControlState::GDBRunning {
+            state: GDBExecutionState::Stopped,
+            last_output: None,
+        };
+
+        feed(&mut data, r#"6^done,value="on""#);
+        assert!(data.non_stop);
+        feed(
+            &mut data,
+            r#"^done,threads=[{id="1",target-id="LWP 10",frame={level="0",addr="0x0000000000401136",func="main",args=[]},state="stopped"},{id="2",target-id="LWP 11",state="running"}],current-thread-id="1""#,
+        );
+
+        // Another thread stops, the one we look at stays selected
+        let line = r#"*stopped,reason="breakpoint-hit",thread-id="2",stopped-threads=["2"],frame={addr="0x0000000000401150",func="worker",args=[]}"#;
+        let next = read_console_input(
+            stopped.clone(),
+            &mut data,
+            &ConsoleOutput::Stdout(line.into()),
+        );
+        match next {
+            SendCommand { commands, .. } => assert_eq!(commands, vec!["-thread-info"]),
+            _ => panic!("{next:?}"),
+        }
+        assert_eq!(data.selected_thread.as_deref(), Some("1"));
+        assert_eq!(data.threads.as_ref().unwrap()[1].state, "stopped");
+
+        // Selecting it moves the buttons along with the thread
+        let running = ControlState::GDBRunning {
+            state: GDBExecutionState::Running,
+            last_output: None,
+        };
+        let select = |prev: &ControlState, id: &str, data: &mut PersistentData| {
+            let (state, cmds) = advance_cmds(&ControlState::select_thread(prev, id, data));
+            assert_eq!(cmds[0], format!("-thread-select {id}"));
+            read_console_input(state, data, &ConsoleOutput::Stdout("^done".into()))
+        };
+        let enabled = |state: &ControlState, data: &PersistentData| {
+            state
+                .buttons_for(data)
+                .iter()
+                .filter(|(label, _)| state.button_enabled(label))
+                .map(|(label, _)| label.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let next = select(&running, "2", &mut data);
+        let buttons = enabled(&next, &data);
+        assert!(buttons.contains(&"Step".to_string()));
+        assert!(!buttons.contains(&"Pause".to_string()));
+
+        feed(&mut data, r#"*running,thread-id="1""#);
+        let next = select(&next, "1", &mut data);
+        let buttons = enabled(&next, &data);
+        assert!(!buttons.contains(&"Step".to_string()));
+        assert!(buttons.contains(&"Pause".to_string()));
+
+        // A new GDB is in all-stop mode until it says otherwise
+        data.gdb_restarted();
+        assert!(!data.non_stop);
+    }
+
+    #[test]
+    fn test_attach_error() {
+        let mut data = PersistentData::default();
+        let stdout = |s: &str| ConsoleOutput::Stdout(s.to_string());
+
+        let (state, _) = advance_cmds(&ControlState::AttachProcessDialog {
+            pid: Some(42),
+            error: None,
+        });
+        let (state, cmds) = advance_cmds(&state);
+        assert_eq!(cmds, START_COMMANDS.to_cmds());
+
+        let state = read_console_input(state, &mut data, &stdout("^done"));
+        let (state, cmds) = advance_cmds(&state);
+        assert_eq!(cmds, vec!["2-target-attach 42"]);
+
+        // `set disassembly-flavor intel` answering, not the attach
+        let state = read_console_input(state, &mut data, &stdout("^done"));
+        assert!(matches!(state, SendCommand { .. }));
+
+        let state = read_console_input(
+            state,
+            &mut data,
+            &stdout(r#"2^error,msg="ptrace: Operation not permitted.""#),
+        );
+        match state {
+            ControlState::AttachProcessDialog {
+                pid: None,
+                error: Some(_),
+            } => {}
+            _ => panic!("{state:?}"),
+        }
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut data = PersistentData::default();
+        let line = format!(
+            r#"{}^done,completion="break",matches=["break"]"#,
+            tokens::COMPLETE
+        );
+
+        // Before a program is loaded
+        let state = read_console_input(
+            ControlState::GDBNothingLoaded,
+            &mut data,
+            &ConsoleOutput::Stdout(line.clone()),
+        );
+        assert_eq!(state, ControlState::GDBNothingLoaded);
+        assert_eq!(data.completion.take().unwrap().1, vec!["break"]);
+
+        // A command waiting for its answer keeps waiting
+        let (waiting, _) = advance_cmds(&ControlState::send_commands(
+            &["-exec-next"],
+            ControlState::no_stderr(ControlState::running_default()),
+        ));
+        let state = read_console_input(waiting.clone(), &mut data, &ConsoleOutput::Stdout(line));
+        assert_eq!(state, waiting);
+        assert!(data.completion.is_some());
+    }
+
+    #[test]
+    fn test_unsent_follow_ups() {
+        let mut data = PersistentData::default();
+        let state = ControlState::send_commands(
+            &["-thread-info"],
+            ControlState::no_stderr(ControlState::GDBExited { status: None }),
+        );
+
+        // Answers a command sent before, the walk over the threads goes out with `-thread-info`
+        let line = r#"^done,thread-ids={thread-id="1"},number-of-threads="1""#;
+        let state = read_console_input(state, &mut data, &ConsoleOutput::Stdout(line.into()));
+        match state {
+            SendCommand {
+                commands,
+                sent: false,
+                ..
+            } => {
+                assert_eq!(commands[0], "-thread-info");
+                assert!(commands.contains(&"-thread-select 1".to_string()));
+            }
+            _ => panic!("{state:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reload_remembers() {
+        let history = vec![
+            "gdb --interpreter=mi3".to_string(),
+            "-inferior-tty-set /dev/pts/9".to_string(),
+        ];
+
+        assert_eq!(
+            recover_commands(&history),
+            [
+                "quit",
+                "pwd",
+                "gdb --interpreter=mi3",
+                "-inferior-tty-set /dev/pts/9"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reload_relocks() {
+        let mut data = PersistentData {
+            scheduler_locked: true,
+            ..Default::default()
+        };
+        let stop = |data: &mut PersistentData| {
+            let line = r#"*stopped,reason="breakpoint-hit",frame={addr="0x0000000000401136",func="main",args=[]},thread-id="1",stopped-threads="all""#;
+            match read_console_input(
+                ControlState::running_default(),
+                data,
+                &ConsoleOutput::Stdout(line.into()),
+            ) {
+                SendCommand { commands, .. } => commands,
+                other => panic!("{other:?}"),
+            }
+        };
+        let relocks = |cmds: Vec<String>| cmds.contains(&"-gdb-set scheduler-locking step".into());
+
+        // The same GDB keeps it
+        assert!(!relocks(stop(&mut data)));
+
+        // Only the first stop of a new one needs it
+        data.gdb_restarted();
+        assert!(relocks(stop(&mut data)));
+        assert!(!relocks(stop(&mut data)));
+
+        data.scheduler_locked = false;
+        data.gdb_restarted();
+        assert!(!relocks(stop(&mut data)));
+    }
+
+    #[test]
+    fn test_run_to_line() {
+        let (_, cmds) = advance_cmds(&ControlState::run_to_line(Path::new("main.c"), 4, false));
+        assert_eq!(cmds, vec![r#"-exec-until "main.c:4""#]);
+
+        let (_, cmds) = advance_cmds(&ControlState::run_to_line(Path::new("main.c"), 4, true));
+        assert_eq!(
+            cmds,
+            vec![r#"-break-insert -t -h "main.c:4""#, "-exec-continue"]
+        );
+
+        let (_, cmds) = advance_cmds(&ControlState::run_to_line(
+            Path::new("my src/a.c"),
+            4,
+            false,
+        ));
+        assert_eq!(cmds, vec![r#"-exec-until "my src/a.c:4""#]);
+    }
+
+    #[test]
+    fn test_asm_only() {
+        let mut data = PersistentData::default();
+        let state = ControlState::running_default();
+        let step = |data: &PersistentData| {
+            let (_, f) = state
+                .buttons_for(data)
+                .into_iter()
+                .find(|(l, _)| *l == "Step")
+                .unwrap();
+            match f(&state, &[]) {
+                SendCommand { commands, .. } => commands,
+                _ => vec![],
+            }
+        };
+
+        feed(
+            &mut data,
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="fib"}]"#,
+        );
+        // A source that's there, wherever the tests run from
+        let dir = std::env::temp_dir().join(format!("iron-dbg-asm-only-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("fib.c");
+        std::fs::write(&source, "int fib(int n) {}\n").unwrap();
+        feed(
+            &mut data,
+            &format!(
+                r#"*stopped,reason="end-stepping-range",frame={{addr="0x0000000000401136",func="fib",args=[],file="fib.c",fullname="{}",line="8"}},thread-id="1",stopped-threads="all""#,
+                mi::escape(&source.display().to_string())
+            ),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!data.asm_only());
+        assert_eq!(step(&data), vec!["-exec-step"]);
+
+        // Stepped into something without line information, the last file mustn't stay
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x00007ffff7e3c6f0",func="puts",args=[]},thread-id="1",stopped-threads="all""#,
+        );
+        assert!(data.file.is_none());
+        assert!(data.asm_only());
+        assert_eq!(step(&data), vec!["-exec-step-instruction"]);
+
+        // A path from the machine it was built on
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x0000000000401136",func="fib",args=[],file="fib.c",fullname="/build/nowhere/fib.c",line="8"},thread-id="1",stopped-threads="all""#,
+        );
+        assert!(data.asm_only());
+    }
+
+    #[test]
+    fn test_crash_report_offered() {
+        let mut data = PersistentData::default();
+        let offered = |state: &ControlState, data: &PersistentData| {
+            state
+                .buttons_for(data)
+                .iter()
+                .any(|(l, _)| *l == "Generate crash report")
+        };
+        let stopped = ControlState::GDBRunning {
+            state: GDBExecutionState::Stopped,
+            last_output: None,
+        };
+
+        feed(
+            &mut data,
+            r#"*stopped,reason="end-stepping-range",frame={addr="0x0000000000401136",func="fib",args=[]},thread-id="1",stopped-threads="all""#,
+        );
+        assert!(!offered(&stopped, &data));
+
+        feed(
+            &mut data,
+            r#"*stopped,reason="signal-received",signal-name="SIGSEGV",signal-meaning="Segmentation fault",frame={addr="0x0000000000401136",func="fib",args=[]},thread-id="1",stopped-threads="all""#,
+        );
+        assert!(offered(&stopped, &data));
+
+        // The process is gone, there's nothing left to walk
+        let exited = ControlState::GDBExited { status: None };
+        assert!(!offered(&exited, &data));
+
+        let core = ControlState::GDBRunning {
+            state: GDBExecutionState::PostMortem,
+            last_output: None,
+        };
+        assert!(offered(&core, &PersistentData::default()));
+    }
+
+    #[test]
+    fn test_is_stopped() {
+        let running = |state| ControlState::GDBRunning {
+            state,
+            last_output: None,
+        };
+        assert!(running(GDBExecutionState::Stopped).is_stopped());
+        assert!(!running(GDBExecutionState::Running).is_stopped());
+        assert!(!running(GDBExecutionState::PostMortem).is_stopped());
+        // The buttons are enabled here, but there's nothing to write to
+        assert!(ControlState::GDBNothingLoaded.button_enabled("Step"));
+        assert!(!ControlState::GDBNothingLoaded.is_stopped());
+        assert!(!ControlState::GDBExited { status: None }.is_stopped());
     }
 }
@@ -0,0 +1,260 @@
+use crate::mi_types::Instruction;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::tokens::{DISASSEMBLE_FUNCTION, DISASSEMBLE_RANGE};
+
+/// Bytes disassembled after the address when there's no function
+const RANGE_SIZE: u64 = 128;
+
+/// What the disassembly view shows, it goes back to the PC of the selected frame on every stop
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Disassembly {
+    /// Source lines, when there are any, come with the instructions they were compiled to
+    pub instructions: Vec<Instruction>,
+    /// Text of the source lines of `instructions` by file and line, read when they arrive rather
+    /// than on every frame
+    pub source: HashMap<(String, u32), String>,
+    /// Address we navigated to, `None` follows `$pc`
+    pub at: Option<String>,
+    pub error: Option<String>,
+    /// Breakpoint numbers and the address each one is at
+    pub breakpoints: Vec<(String, u64)>,
+    /// Breakpoints are set with `-h`, as kernels need
+    pub hardware: bool,
+}
+
+/// What can be done from the disassembly view
+#[derive(Clone, PartialEq, Debug)]
+pub enum DisassemblyEdit {
+    Goto(u64),
+    FollowPc,
+    Break(u64),
+    Delete(String),
+}
+
+impl Disassembly {
+    pub fn command(&self) -> String {
+        format!(
+            "{DISASSEMBLE_FUNCTION}-data-disassemble -a \"{}\" -- 4",
+            self.address()
+        )
+    }
+
+    fn address(&self) -> &str {
+        self.at.as_deref().unwrap_or("$pc")
+    }
+
+    pub fn answered(&mut self, instructions: Vec<Instruction>) {
+        self.instructions = instructions;
+        self.error = None;
+        self.read_source();
+    }
+
+    /// Each file is read once, files that aren't on this machine leave their lines empty
+    fn read_source(&mut self) {
+        let mut files: HashMap<&str, Option<Vec<String>>> = HashMap::new();
+        self.source.clear();
+
+        for i in &self.instructions {
+            if let (Some(file), Some(line)) = (&i.file, i.line) {
+                let text = files
+                    .entry(file)
+                    .or_insert_with(|| {
+                        fs::read_to_string(file)
+                            .ok()
+                            .map(|s| s.lines().map(str::to_string).collect())
+                    })
+                    .as_ref()
+                    .and_then(|lines| lines.get(line.saturating_sub(1) as usize))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_default();
+                self.source.insert((file.clone(), line), text);
+            }
+        }
+    }
+
+    pub fn source_line(&self, file: &str, line: u32) -> &str {
+        self.source
+            .get(&(file.to_string(), line))
+            .map_or("", String::as_str)
+    }
+
+    /// An error for one of our tokens, returns the command to try next if there's one
+    pub fn failed(&mut self, token: Option<u64>, msg: String) -> Option<String> {
+        match token {
+            Some(DISASSEMBLE_FUNCTION) => {
+                let at = self.address();
+                Some(format!(
+                    "{DISASSEMBLE_RANGE}-data-disassemble -s \"{at}\" -e \"{at} + {RANGE_SIZE}\" -- 4"
+                ))
+            }
+            Some(DISASSEMBLE_RANGE) => {
+                self.instructions.clear();
+                self.error = Some(msg);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// A breakpoint was set or changed, `<PENDING>` and `<MULTIPLE>` ones have no address
+    pub fn breakpoint_set(&mut self, number: String, addr: u64) {
+        self.breakpoints.retain(|(n, _)| *n != number);
+        self.breakpoints.push((number, addr));
+    }
+
+    pub fn breakpoint_deleted(&mut self, number: &str) {
+        self.breakpoints.retain(|(n, _)| n != number);
+    }
+
+    pub fn breakpoint_at(&self, addr: u64) -> Option<&str> {
+        self.breakpoints
+            .iter()
+            .find(|(_, a)| *a == addr)
+            .map(|(n, _)| n.as_str())
+    }
+}
+
+/// Changes what the view shows and returns the commands that go with it
+pub fn apply(disassembly: &mut Disassembly, edit: DisassemblyEdit) -> Vec<String> {
+    match edit {
+        DisassemblyEdit::Goto(addr) => {
+            disassembly.at = Some(format!("{addr:#x}"));
+            vec![disassembly.command()]
+        }
+        DisassemblyEdit::FollowPc => {
+            disassembly.at = None;
+            vec![disassembly.command()]
+        }
+        DisassemblyEdit::Break(addr) => {
+            let h = if disassembly.hardware { "-h " } else { "" };
+            vec![format!("-break-insert {h}*{addr:#x}")]
+        }
+        // GDB doesn't tell us about deletions it was asked for
+        DisassemblyEdit::Delete(number) => {
+            disassembly.breakpoint_deleted(&number);
+            vec![format!("-break-delete {number}")]
+        }
+    }
+}
+
+/// The address a jump or a call goes to, and the symbol GDB printed for it, as in
+/// `call   0x401126 <fib>`
+pub fn branch_target(inst: &str) -> Option<(u64, Option<&str>)> {
+    let mut words = inst.split_whitespace();
+    // Prefixes like `bnd` and `notrack` come before the mnemonic
+    let branch = words
+        .by_ref()
+        .take(2)
+        .any(|w| is_branch(w.trim_end_matches(',')));
+    if !branch {
+        return None;
+    }
+
+    // An operand that is only an address, `[rip+0x2fe2]` is read from memory at run time
+    let addr = words.find_map(|w| {
+        let digits = w.trim_end_matches(',').strip_prefix("0x")?;
+        u64::from_str_radix(digits, 16).ok()
+    })?;
+
+    let symbol = inst
+        .split_once('<')
+        .and_then(|(_, s)| s.split_once('>'))
+        .map(|(s, _)| s);
+
+    Some((addr, symbol))
+}
+
+/// x86 jumps, calls and loops and the ARM branches
+fn is_branch(mnemonic: &str) -> bool {
+    mnemonic.starts_with('j')
+        || mnemonic.starts_with("call")
+        || mnemonic.starts_with("loop")
+        || matches!(
+            mnemonic,
+            "b" | "bl" | "blr" | "br" | "cbz" | "cbnz" | "tbz" | "tbnz"
+        )
+        || mnemonic.starts_with("b.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let mut d = Disassembly::default();
+        assert_eq!(d.command(), r#"4-data-disassemble -a "$pc" -- 4"#);
+
+        // No function there, try a range
+        let cmd = d.failed(Some(4), "No function contains specified address.".into());
+        assert_eq!(
+            cmd.as_deref(),
+            Some(r#"5-data-disassemble -s "$pc" -e "$pc + 128" -- 4"#)
+        );
+        assert_eq!(d.failed(Some(5), "Cannot access memory".into()), None);
+        assert!(d.error.is_some());
+
+        let cmds = apply(&mut d, DisassemblyEdit::Goto(0x401126));
+        assert_eq!(cmds, vec![r#"4-data-disassemble -a "0x401126" -- 4"#]);
+
+        d.breakpoint_set("2".into(), 0x401130);
+        assert_eq!(d.breakpoint_at(0x401130), Some("2"));
+        let cmds = apply(&mut d, DisassemblyEdit::Delete("2".into()));
+        assert_eq!(cmds, vec!["-break-delete 2"]);
+        assert_eq!(d.breakpoint_at(0x401130), None);
+
+        d.hardware = true;
+        let cmds = apply(&mut d, DisassemblyEdit::Break(0xffffffff81000000));
+        assert_eq!(cmds, vec!["-break-insert -h *0xffffffff81000000"]);
+    }
+
+    #[test]
+    fn test_source() {
+        let path = std::env::temp_dir().join(format!("iron-dbg-source-{}.c", std::process::id()));
+        fs::write(&path, "int main() {\n    return 0;\n}\n").unwrap();
+        let file = path.to_string_lossy().to_string();
+
+        let inst = |address, file: &str, line| Instruction {
+            address,
+            func: Some("main".into()),
+            offset: None,
+            inst: "nop".into(),
+            file: Some(file.into()),
+            line: Some(line),
+        };
+        let mut d = Disassembly::default();
+        d.answered(vec![
+            inst(0x401126, &file, 1),
+            inst(0x40112a, &file, 2),
+            inst(0x401130, "/nowhere/a.c", 3),
+        ]);
+        fs::remove_file(&path).unwrap();
+
+        // Already read, the file going away doesn't matter
+        assert_eq!(d.source_line(&file, 2), "return 0;");
+        assert_eq!(d.source_line(&file, 1), "int main() {");
+        assert_eq!(d.source_line("/nowhere/a.c", 3), "");
+    }
+
+    #[test]
+    fn test_branch_target() {
+        assert_eq!(
+            branch_target("call   0x401126 <fib>"),
+            Some((0x401126, Some("fib")))
+        );
+        assert_eq!(
+            branch_target("jne    0x40115a <main+36>"),
+            Some((0x40115a, Some("main+36")))
+        );
+        assert_eq!(branch_target("bnd jmp 0x401020"), Some((0x401020, None)));
+        assert_eq!(
+            branch_target("bl\t0x400554 <puts@plt>"),
+            Some((0x400554, Some("puts@plt")))
+        );
+        assert_eq!(branch_target("call   QWORD PTR [rip+0x2fe2]"), None);
+        assert_eq!(branch_target("mov    eax,0x0"), None);
+        assert_eq!(branch_target("jmp    rax"), None);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::config::{self, field_or, KeyValues};
+use crate::launch::exec_arguments;
+use crate::mi::escape;
+use crate::procs::split_args;
+
+pub const GDBSERVER: &str = "gdbserver";
+
+/// How a program is debugged under gdbserver. Saved so the next session starts the same way.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GdbserverConfig {
+    /// We start gdbserver ourselves, otherwise we connect to one that runs with `--multi`
+    pub local: bool,
+    /// `:2345` for a local gdbserver, `host:2345` for a remote one
+    pub address: String,
+    /// The program on this machine, GDB reads its symbols
+    pub program: String,
+    pub args: String,
+    /// Where the program is uploaded to on the target with `remote put`, empty when it's there
+    /// already at the same path
+    pub remote_program: String,
+    /// Where GDB finds the target's libraries, `target:` reads them through gdbserver
+    pub sysroot: String,
+    /// `remote=local` for each file `remote get` brings back after connecting
+    pub fetch: String,
+}
+
+impl Default for GdbserverConfig {
+    fn default() -> GdbserverConfig {
+        GdbserverConfig {
+            local: true,
+            address: ":2345".to_string(),
+            program: String::new(),
+            args: String::new(),
+            remote_program: String::new(),
+            sysroot: String::new(),
+            fetch: String::new(),
+        }
+    }
+}
+
+impl GdbserverConfig {
+    /// The saved configuration, or the default one if there's none
+    pub fn load() -> GdbserverConfig {
+        config::load("gdbserver", GdbserverConfig::default())
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        config::save("gdbserver", self)
+    }
+
+    /// Same order as the fields of `ControlState::GdbserverDialog`, empty ones keep the defaults
+    pub fn from_fields(fields: &[String]) -> GdbserverConfig {
+        let default = GdbserverConfig::default();
+
+        GdbserverConfig {
+            local: fields[0].trim() != "remote",
+            address: field_or(&fields[1], default.address),
+            program: fields[2].trim().to_string(),
+            args: fields[3].trim().to_string(),
+            remote_program: fields[4].trim().to_string(),
+            sysroot: fields[5].trim().to_string(),
+            fetch: fields[6].trim().to_string(),
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.mode().to_string(),
+            self.address.clone(),
+            self.program.clone(),
+            self.args.clone(),
+            self.remote_program.clone(),
+            self.sysroot.clone(),
+            self.fetch.clone(),
+        ]
+    }
+
+    fn mode(&self) -> &str {
+        if self.local {
+            "local"
+        } else {
+            "remote"
+        }
+    }
+
+    /// `gdbserver :PORT prog args`, for the local mode
+    pub fn command_line(&self) -> Vec<String> {
+        let mut args = vec![self.listen(), self.program.clone()];
+        args.extend(split_args(&self.args));
+
+        args
+    }
+
+    /// gdbserver wants `:PORT` to listen on every interface
+    fn listen(&self) -> String {
+        match self.address.rsplit_once(':') {
+            Some((_, port)) => format!(":{port}"),
+            None => format!(":{}", self.address),
+        }
+    }
+
+    /// Where we connect to, `localhost` for the one we start
+    pub fn host(&self) -> String {
+        if self.local {
+            format!("localhost{}", self.listen())
+        } else {
+            self.address.clone()
+        }
+    }
+
+    /// The program as the target sees it
+    fn target_program(&self) -> &str {
+        if self.remote_program.is_empty() {
+            &self.program
+        } else {
+            &self.remote_program
+        }
+    }
+
+    /// Ends stopped at `main`, as a local run does. The local gdbserver already started the
+    /// program, a remote one runs it when we ask. `remote exec-file` lets "Run again" and "Reload"
+    /// start it over either way.
+    pub fn commands(&self) -> Vec<String> {
+        let mut cmds = vec![format!("file \"{}\"", escape(&self.program))];
+
+        if !self.sysroot.is_empty() {
+            cmds.push(format!("set sysroot {}", self.sysroot));
+        }
+
+        cmds.push(format!("target extended-remote {}", self.host()));
+
+        if !self.local && !self.remote_program.is_empty() {
+            cmds.push(format!(
+                "remote put \"{}\" \"{}\"",
+                escape(&self.program),
+                escape(&self.remote_program)
+            ));
+        }
+
+        cmds.push(format!("set remote exec-file {}", self.target_program()));
+        cmds.push(exec_arguments(&self.args));
+
+        for (remote, local) in self.fetched() {
+            cmds.push(format!("remote get {remote} {local}"));
+        }
+
+        if self.local {
+            cmds.push("-break-insert -t main".to_string());
+            cmds.push("-exec-continue".to_string());
+        } else {
+            cmds.push("-exec-run --start".to_string());
+        }
+
+        cmds
+    }
+
+    fn fetched(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fetch
+            .split_whitespace()
+            .filter_map(|f| f.split_once('='))
+            .filter(|(remote, local)| !remote.is_empty() && !local.is_empty())
+    }
+}
+
+impl KeyValues for GdbserverConfig {
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("mode", self.mode().to_string()),
+            ("address", self.address.clone()),
+            ("program", self.program.clone()),
+            ("args", self.args.clone()),
+            ("remote-program", self.remote_program.clone()),
+            ("sysroot", self.sysroot.clone()),
+            ("fetch", self.fetch.clone()),
+        ]
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let field = match key {
+            "mode" => {
+                self.local = value != "remote";
+                return;
+            }
+            "address" => &mut self.address,
+            "program" => &mut self.program,
+            "args" => &mut self.args,
+            "remote-program" => &mut self.remote_program,
+            "sysroot" => &mut self.sysroot,
+            "fetch" => &mut self.fetch,
+            _ => return,
+        };
+        *field = value.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local() {
+        let fields = ["", "2345", "./res/a.out", "-v 3", "", "", ""].map(String::from);
+        let config = GdbserverConfig::from_fields(&fields);
+
+        assert_eq!(
+            config.command_line(),
+            vec![":2345", "./res/a.out", "-v", "3"]
+        );
+        assert_eq!(
+            config.commands(),
+            vec![
+                "file \"./res/a.out\"",
+                "target extended-remote localhost:2345",
+                "set remote exec-file ./res/a.out",
+                r#"-exec-arguments "-v" "3""#,
+                "-break-insert -t main",
+                "-exec-continue",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remote() {
+        let fields = [
+            "remote",
+            "board:2345",
+            "./res/a.out",
+            "",
+            "/tmp/a.out",
+            "/srv/board-root",
+            "/var/log/app.log=./app.log",
+        ]
+        .map(String::from);
+        let config = GdbserverConfig::from_fields(&fields);
+
+        assert_eq!(
+            config.commands(),
+            vec![
+                "file \"./res/a.out\"",
+                "set sysroot /srv/board-root",
+                "target extended-remote board:2345",
+                r#"remote put "./res/a.out" "/tmp/a.out""#,
+                "set remote exec-file /tmp/a.out",
+                "-exec-arguments ",
+                "remote get /var/log/app.log ./app.log",
+                "-exec-run --start",
+            ]
+        );
+        assert_eq!(
+            config::parse(GdbserverConfig::default(), &config::serialize(&config)),
+            config
+        );
+    }
+}
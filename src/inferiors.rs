@@ -0,0 +1,207 @@
+use crate::mi_types::AsyncInfo;
+use crate::tokens::{self, INFERIOR_SETTINGS};
+
+/// What GDB does when a process forks or execs, with the values each setting takes. The first
+/// value is GDB's default.
+pub const SETTINGS: &[(&str, &[&str])] = &[
+    ("follow-fork-mode", &["parent", "child"]),
+    ("detach-on-fork", &["on", "off"]),
+    ("follow-exec-mode", &["same", "new"]),
+];
+
+/// A thread group, as GDB calls the processes it debugs
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Inferior {
+    /// `i1`, `i2`...
+    pub id: String,
+    /// `None` until it runs and after it exits
+    pub pid: Option<String>,
+    pub executable: Option<String>,
+    pub exit_code: Option<String>,
+    /// Ids of its threads, from `=thread-created`
+    pub threads: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Inferiors {
+    pub groups: Vec<Inferior>,
+    /// Values of `SETTINGS`, in the same order
+    pub settings: Vec<String>,
+}
+
+impl Default for Inferiors {
+    fn default() -> Inferiors {
+        Inferiors {
+            groups: vec![],
+            settings: SETTINGS.iter().map(|(_, v)| v[0].to_string()).collect(),
+        }
+    }
+}
+
+impl Inferiors {
+    /// Keeps the tree in line with GDB's notifications, returns the commands that ask for what
+    /// they don't say
+    pub fn notified(&mut self, info: AsyncInfo) -> Vec<String> {
+        match info {
+            AsyncInfo::ThreadGroupAdded { id } => {
+                self.group(&id);
+            }
+            AsyncInfo::ThreadGroupRemoved { id } => self.groups.retain(|g| g.id != id),
+            AsyncInfo::ThreadGroupStarted { id, pid } => {
+                let g = self.group(&id);
+                g.pid = Some(pid);
+                g.exit_code = None;
+                // The executable changes with `follow-exec-mode same`
+                return vec!["-list-thread-groups".to_string()];
+            }
+            AsyncInfo::ThreadGroupExited { id, exit_code } => {
+                let g = self.group(&id);
+                g.pid = None;
+                g.exit_code = exit_code;
+                g.threads.clear();
+            }
+            AsyncInfo::ThreadCreated { id, group_id } => self.group(&group_id).threads.push(id),
+            AsyncInfo::ThreadExited { id, group_id } => {
+                self.group(&group_id).threads.retain(|t| *t != id);
+            }
+            _ => {}
+        }
+
+        vec![]
+    }
+
+    /// The group `id`, added if we missed its `=thread-group-added`, as happens with `i1` that
+    /// exists from the start
+    fn group(&mut self, id: &str) -> &mut Inferior {
+        let i = match self.groups.iter().position(|g| g.id == id) {
+            Some(i) => i,
+            None => {
+                self.groups.push(Inferior {
+                    id: id.to_string(),
+                    ..Default::default()
+                });
+                self.groups.len() - 1
+            }
+        };
+
+        &mut self.groups[i]
+    }
+
+    /// The answer to `-list-thread-groups`, it knows the executables
+    pub fn listed(&mut self, listed: Vec<Inferior>) {
+        for l in listed {
+            let g = self.group(&l.id);
+            g.pid = l.pid.or(g.pid.take());
+            g.executable = l.executable.or(g.executable.take());
+        }
+    }
+
+    pub fn setting_answered(&mut self, token: u64, value: String) {
+        let i = match tokens::index(INFERIOR_SETTINGS, Some(token)) {
+            Some(i) => i as usize,
+            None => return,
+        };
+        if let Some(s) = self.settings.get_mut(i).filter(|_| i < SETTINGS.len()) {
+            *s = value;
+        }
+    }
+
+    /// Threads `=thread-created` didn't tell us about belong to the first group
+    pub fn group_of(&self, thread: &str) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|g| g.threads.iter().any(|t| t == thread))
+            .or_else(|| self.groups.first())
+            .map(|g| g.id.as_str())
+    }
+}
+
+/// Changes the setting at index `i` of `SETTINGS` and reads it back
+pub fn set_commands(i: usize, value: &str) -> Vec<String> {
+    let (name, _) = SETTINGS[i];
+    let token = tokens::nth(INFERIOR_SETTINGS, i as u64);
+
+    vec![
+        format!("-gdb-set {name} {value}"),
+        format!("{token}-gdb-show {name}"),
+    ]
+}
+
+/// `inferior N` has no MI command, GDB tells us about the thread it selects with
+/// `=thread-selected`
+pub fn switch_commands(id: &str) -> Vec<String> {
+    vec![
+        format!(
+            "-interpreter-exec console \"inferior {}\"",
+            id.trim_start_matches('i')
+        ),
+        "-thread-info".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notified() {
+        let mut inferiors = Inferiors::default();
+
+        inferiors.notified(AsyncInfo::ThreadCreated {
+            id: "1".into(),
+            group_id: "i1".into(),
+        });
+        let cmds = inferiors.notified(AsyncInfo::ThreadGroupStarted {
+            id: "i1".into(),
+            pid: "100".into(),
+        });
+        assert_eq!(cmds, vec!["-list-thread-groups"]);
+
+        // The daemon forks a worker
+        inferiors.notified(AsyncInfo::ThreadGroupAdded { id: "i2".into() });
+        inferiors.notified(AsyncInfo::ThreadGroupStarted {
+            id: "i2".into(),
+            pid: "101".into(),
+        });
+        inferiors.notified(AsyncInfo::ThreadCreated {
+            id: "2".into(),
+            group_id: "i2".into(),
+        });
+        assert_eq!(inferiors.groups.len(), 2);
+        assert_eq!(inferiors.group_of("2"), Some("i2"));
+        assert_eq!(inferiors.group_of("7"), Some("i1"));
+
+        inferiors.listed(vec![Inferior {
+            id: "i2".into(),
+            executable: Some("/usr/sbin/worker".into()),
+            ..Default::default()
+        }]);
+        assert_eq!(inferiors.groups[1].pid.as_deref(), Some("101"));
+
+        inferiors.notified(AsyncInfo::ThreadGroupExited {
+            id: "i2".into(),
+            exit_code: Some("3".into()),
+        });
+        assert!(inferiors.groups[1].threads.is_empty());
+        assert_eq!(inferiors.groups[1].exit_code.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut inferiors = Inferiors::default();
+        assert_eq!(inferiors.settings, vec!["parent", "on", "same"]);
+
+        assert_eq!(
+            set_commands(1, "off"),
+            vec!["-gdb-set detach-on-fork off", "11-gdb-show detach-on-fork"]
+        );
+        inferiors.setting_answered(11, "off".into());
+        inferiors.setting_answered(1000, "x".into());
+        assert_eq!(inferiors.settings, vec!["parent", "off", "same"]);
+
+        assert_eq!(
+            switch_commands("i2")[0],
+            r#"-interpreter-exec console "inferior 2""#
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::config::{self, field_or, KeyValues};
+
+/// What `set architecture` is offered, `auto` leaves it to the target description. `i8086` is for
+/// the real-mode start of the boot, `i386` for the protected-mode part before long mode.
+pub const ARCHITECTURES: &[&str] = &[
+    "auto",
+    "i8086",
+    "i386",
+    "i386:x86-64",
+    "aarch64",
+    "arm",
+    "riscv:rv64",
+];
+
+/// How we attach to a kernel behind a gdbstub. Saved so the next session attaches the same way.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KernelConfig {
+    pub host: String,
+    /// Usually `vmlinux`, the image that boots has no symbols
+    pub symbol_file: String,
+    /// Where KASLR moved the kernel, as in `0x1e000000`
+    pub kaslr_offset: String,
+    /// `path@address` for each module, the address is the one of its `.text`
+    pub modules: String,
+    pub architecture: String,
+    /// Software breakpoints are written to memory that isn't mapped yet early in the boot
+    pub hardware_breakpoints: bool,
+}
+
+impl Default for KernelConfig {
+    fn default() -> KernelConfig {
+        KernelConfig {
+            host: "127.0.0.1:1234".to_string(),
+            symbol_file: String::new(),
+            kaslr_offset: String::new(),
+            modules: String::new(),
+            architecture: ARCHITECTURES[0].to_string(),
+            hardware_breakpoints: true,
+        }
+    }
+}
+
+impl KernelConfig {
+    /// The saved configuration, or the default one if there's none
+    pub fn load() -> KernelConfig {
+        config::load("kernel", KernelConfig::default())
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        config::save("kernel", self)
+    }
+
+    /// Same order as the fields of `ControlState::TryAttachPort`
+    pub fn from_fields(fields: &[String]) -> KernelConfig {
+        let default = KernelConfig::default();
+
+        KernelConfig {
+            host: field_or(&fields[0], default.host),
+            symbol_file: fields[1].trim().to_string(),
+            kaslr_offset: fields[2].trim().to_string(),
+            modules: fields[3].trim().to_string(),
+            architecture: field_or(&fields[4], default.architecture),
+            hardware_breakpoints: fields[5].trim() != "off",
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.host.clone(),
+            self.symbol_file.clone(),
+            self.kaslr_offset.clone(),
+            self.modules.clone(),
+            self.architecture.clone(),
+            on_off(self.hardware_breakpoints).to_string(),
+        ]
+    }
+
+    /// Symbols are loaded before connecting so the first stop already has them, the architecture
+    /// is set after because connecting sets it from the target description
+    pub fn commands(&self) -> Vec<String> {
+        let mut cmds = vec![];
+
+        if !self.symbol_file.is_empty() {
+            if self.kaslr_offset.is_empty() {
+                cmds.push(format!("symbol-file {}", self.symbol_file));
+            } else {
+                cmds.push(format!(
+                    "symbol-file {} -o {}",
+                    self.symbol_file, self.kaslr_offset
+                ));
+            }
+        }
+
+        for (path, addr) in self.modules() {
+            cmds.push(format!("add-symbol-file {path} {addr}"));
+        }
+
+        cmds.push(format!("target remote {}", self.host));
+
+        if self.architecture != "auto" {
+            cmds.push(format!("set architecture {}", self.architecture));
+        }
+
+        cmds
+    }
+
+    /// Modules are separated by whitespace or commas, the ones without an address are skipped
+    fn modules(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.modules
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter_map(|m| m.rsplit_once('@'))
+            .filter(|(path, addr)| !path.is_empty() && !addr.is_empty())
+    }
+}
+
+impl KeyValues for KernelConfig {
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("host", self.host.clone()),
+            ("symbol-file", self.symbol_file.clone()),
+            ("kaslr-offset", self.kaslr_offset.clone()),
+            ("modules", self.modules.clone()),
+            ("architecture", self.architecture.clone()),
+            ("hbreak", on_off(self.hardware_breakpoints).to_string()),
+        ]
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let field = match key {
+            "hbreak" => {
+                self.hardware_breakpoints = value != "off";
+                return;
+            }
+            "host" => &mut self.host,
+            "symbol-file" => &mut self.symbol_file,
+            "kaslr-offset" => &mut self.kaslr_offset,
+            "modules" => &mut self.modules,
+            "architecture" => &mut self.architecture,
+            _ => return,
+        };
+        *field = value.to_string();
+    }
+}
+
+fn on_off(b: bool) -> &'static str {
+    if b {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let fields = [
+            "",
+            "vmlinux",
+            "0x1e000000",
+            "drivers/net/e1000.ko@0xffffffffc0000000, fs/ext4.ko@0xffffffffc0200000 broken.ko",
+            "i386",
+            "",
+        ]
+        .map(String::from);
+        let config = KernelConfig::from_fields(&fields);
+
+        assert!(config.hardware_breakpoints);
+        assert_eq!(
+            config.commands(),
+            vec![
+                "symbol-file vmlinux -o 0x1e000000",
+                "add-symbol-file drivers/net/e1000.ko 0xffffffffc0000000",
+                "add-symbol-file fs/ext4.ko 0xffffffffc0200000",
+                "target remote 127.0.0.1:1234",
+                "set architecture i386",
+            ]
+        );
+        assert_eq!(
+            config::parse(KernelConfig::default(), &config::serialize(&config)),
+            config
+        );
+
+        let config =
+            KernelConfig::from_fields(&["localhost:1234", "", "", "", "", "off"].map(String::from));
+        assert!(!config.hardware_breakpoints);
+        assert_eq!(config.commands(), vec!["target remote localhost:1234"]);
+    }
+}
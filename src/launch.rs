@@ -0,0 +1,216 @@
+use crate::config::{self, KeyValues};
+use crate::mi::escape;
+use crate::procs::split_args;
+use crate::tokens::NON_STOP;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Everything we need to start a binary besides its path.
+/// It's saved per binary so the next session starts the same way.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct LaunchConfig {
+    pub path: String,
+    pub args: String,
+    pub cwd: String,
+    pub env: Vec<(String, String)>,
+    /// Threads stop and run on their own instead of all at once
+    pub non_stop: bool,
+}
+
+impl LaunchConfig {
+    pub fn new(path: &str) -> LaunchConfig {
+        LaunchConfig {
+            path: path.to_string(),
+            ..LaunchConfig::default()
+        }
+    }
+
+    /// The saved configuration for `path`, or an empty one if there's none
+    pub fn load(path: &str) -> LaunchConfig {
+        config::load(config_name(path), LaunchConfig::new(path))
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        config::save(config_name(&self.path), self)
+    }
+
+    /// Same order as the fields of `ControlState::LaunchDialog`
+    pub fn from_fields(path: &str, fields: &[String]) -> LaunchConfig {
+        LaunchConfig {
+            path: path.to_string(),
+            args: fields[0].trim().to_string(),
+            cwd: fields[1].trim().to_string(),
+            env: parse_env(&fields[2]),
+            non_stop: matches!(fields[3].trim(), "on" | "yes" | "y" | "true"),
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<String> {
+        let env = self
+            .env
+            .iter()
+            .map(|(k, v)| format!("{k}={}", quote_arg(v)))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let non_stop = if self.non_stop { "on" } else { "off" };
+
+        vec![
+            self.args.clone(),
+            self.cwd.clone(),
+            env,
+            non_stop.to_string(),
+        ]
+    }
+
+    /// The commands that set up the inferior, they have to be sent before it starts
+    pub fn commands(&self) -> Vec<String> {
+        let mut cmds = vec![
+            format!("file \"{}\"", escape(&self.path)),
+            exec_arguments(&self.args),
+        ];
+
+        if !self.cwd.is_empty() {
+            cmds.push(format!("-environment-cd \"{}\"", escape(&self.cwd)));
+        }
+
+        // The value is everything after the `=`, spaces included
+        for (k, v) in &self.env {
+            cmds.push(format!(
+                "-interpreter-exec console \"set environment {}\"",
+                escape(&format!("{k}={v}"))
+            ));
+        }
+
+        // It can't be changed once the inferior runs
+        if self.non_stop {
+            cmds.push("-gdb-set non-stop on".to_string());
+            cmds.push(format!("{NON_STOP}-gdb-show non-stop"));
+        }
+
+        cmds
+    }
+}
+
+/// The path is the name of the file, it isn't saved in it
+impl KeyValues for LaunchConfig {
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            ("args", self.args.clone()),
+            ("cwd", self.cwd.clone()),
+            ("non-stop", self.non_stop.to_string()),
+        ];
+        entries.extend(self.env.iter().map(|(k, v)| ("env", format!("{k}={v}"))));
+        entries
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "args" => self.args = value.to_string(),
+            "cwd" => self.cwd = value.to_string(),
+            "env" => self.env.extend(
+                value
+                    .split_once('=')
+                    .map(|(k, v)| (k.to_string(), v.to_string())),
+            ),
+            "non-stop" => self.non_stop = value == "true",
+            _ => {}
+        }
+    }
+}
+
+/// `FOO=1 BAR=2` as the pairs `(FOO, 1)` and `(BAR, 2)`
+fn parse_env(src: &str) -> Vec<(String, String)> {
+    split_args(src)
+        .iter()
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// `-exec-arguments` with each of `args`, split as a shell would, quoted on its own
+pub fn exec_arguments(args: &str) -> String {
+    let args = split_args(args)
+        .iter()
+        .map(|a| format!("\"{}\"", escape(a)))
+        .collect::<Vec<_>>();
+
+    format!("-exec-arguments {}", args.join(" "))
+}
+
+/// `v` as `parse_env` reads it back
+fn quote_arg(v: &str) -> String {
+    if v.is_empty() || v.contains(|c: char| c.is_whitespace() || "\"'\\".contains(c)) {
+        format!("\"{}\"", escape(v))
+    } else {
+        v.to_string()
+    }
+}
+
+/// One file per binary, named after its absolute path
+fn config_name(binary: &str) -> PathBuf {
+    let binary = fs::canonicalize(binary).unwrap_or_else(|_| PathBuf::from(binary));
+    let name = binary
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "_");
+
+    Path::new("launch").join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let fields = vec![
+            "--verbose input.txt".to_string(),
+            "/tmp".to_string(),
+            "FOO=1  BAR=\"two words\"".to_string(),
+            "off".to_string(),
+        ];
+        let config = LaunchConfig::from_fields("./res/a.out", &fields);
+
+        assert_eq!(
+            config.env,
+            vec![
+                ("FOO".to_string(), "1".to_string()),
+                ("BAR".to_string(), "two words".to_string())
+            ]
+        );
+        assert_eq!(config.to_fields()[2], "FOO=1 BAR=\"two words\"");
+        assert_eq!(
+            config.commands(),
+            vec![
+                "file \"./res/a.out\"",
+                r#"-exec-arguments "--verbose" "input.txt""#,
+                "-environment-cd \"/tmp\"",
+                r#"-interpreter-exec console "set environment FOO=1""#,
+                r#"-interpreter-exec console "set environment BAR=two words""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let config = LaunchConfig {
+            path: "./res/a.out".to_string(),
+            args: "a=b c".to_string(),
+            cwd: "".to_string(),
+            env: vec![("GREETING".to_string(), "a=b c".to_string())],
+            non_stop: true,
+        };
+
+        assert_eq!(
+            config::parse(
+                LaunchConfig::new("./res/a.out"),
+                &config::serialize(&config)
+            ),
+            config
+        );
+        assert_eq!(
+            config.commands()[3..],
+            ["-gdb-set non-stop on", "6-gdb-show non-stop"]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use eframe::epaint::Vec2;
 use std::error;
 use std::fmt::{Debug, Display, Formatter, Pointer};
 use std::fs::read;
+use std::path::Path;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -18,12 +19,30 @@ use tokio::{
     sync::mpsc,
 };
 
+mod command_line;
+mod config;
 mod control;
+mod disassembly;
+mod gdbserver;
+mod inferiors;
+mod kernel;
+mod launch;
+mod memory;
 mod mi;
 mod mi_parse;
 mod mi_types;
+mod procs;
+mod qemu;
 mod query;
+mod registers;
+mod repl;
+mod report;
+mod terminal;
+mod tokens;
+mod transcript;
 mod ui;
+mod varobj;
+mod watch;
 
 use crate::control::{ControlState, InputCommand};
 
@@ -60,16 +79,40 @@ pub enum ConsoleOutput {
 
 struct MyApp {
     code: String,
-    user_input: String,
+    command_line: command_line::CommandLine,
 
     sender: mpsc::Sender<InputCommand>,
     reader_handle: tokio::task::JoinHandle<()>,
 
-    console_output: Arc<Mutex<String>>,
+    /// What we sent GDB and what it answered, as command blocks
+    transcript: Arc<Mutex<transcript::Transcript>>,
+    console_view: transcript::ConsoleView,
     input_fields: Vec<String>,
+    /// Which kind of state `input_fields` were filled for
+    input_kind: Option<std::mem::Discriminant<ControlState>>,
     gdb_state: Arc<Mutex<control::ControlState>>,
     persistent_data: Arc<Mutex<PersistentData>>,
     state_history: Arc<Mutex<History<control::ControlState>>>,
+    /// Set on `setup`, lets the console reader wake the UI up when GDB talks by itself
+    repaint: Arc<Mutex<Option<egui::Context>>>,
+    watch_input: String,
+    /// The watch we just added, until the user says whether it should be a watchpoint
+    watch_offer: Option<usize>,
+    register_view: registers::RegisterView,
+    memory_view: memory::MemoryView,
+    repl_view: repl::ReplView,
+    processes: procs::ProcessList,
+    /// The QEMU or the gdbserver we debug in and the title of its console, it goes away with the
+    /// session
+    target: Mutex<Option<(&'static str, procs::Child)>>,
+    target_input: String,
+    /// The program's own terminal, `None` if there's no pty to be had
+    terminal: Option<terminal::Pty>,
+    terminal_view: terminal::TerminalView,
+    /// A program is loaded, its terminal stays up until the session is over
+    session: bool,
+    /// Whether GDB was told to run programs in `terminal`
+    tty_set: bool,
 }
 
 impl MyApp {
@@ -77,8 +120,8 @@ impl MyApp {
         sender: mpsc::Sender<InputCommand>,
         mut receiver: mpsc::Receiver<ConsoleOutput>,
     ) -> MyApp {
-        let console_handle = Arc::new(Mutex::new(String::new()));
-        let reader_console_handle = console_handle.clone();
+        let transcript = Arc::new(Mutex::new(transcript::Transcript::default()));
+        let reader_transcript = transcript.clone();
 
         let gdb_state_handle = Arc::new(Mutex::new(ControlState::new()));
         let reader_gdb_handle = gdb_state_handle.clone();
@@ -86,43 +129,63 @@ impl MyApp {
         let gdb_state_hist = Arc::new(Mutex::new(History::new()));
         let gdb_state_hist_console = gdb_state_hist.clone();
 
-        let persistent_data = Arc::new(Mutex::new(PersistentData::default()));
+        let persistent_data = Arc::new(Mutex::new(PersistentData {
+            watches: watch::load(),
+            ..Default::default()
+        }));
         let p_data_handle = persistent_data.clone();
 
+        let repaint: Arc<Mutex<Option<egui::Context>>> = Arc::new(Mutex::new(None));
+        let reader_repaint = repaint.clone();
+
         let consume_console_handle = tokio::spawn(async move {
             // @TODO: There's sometimes a big delay to receive the output that comes out of the console.
             while let Some(cmd) = receiver.recv().await {
-                let mut console_out = reader_console_handle.lock().unwrap();
-
-                let cur_state = {
-                    let s = reader_gdb_handle.lock().unwrap().clone();
-                    s
-                };
-
-                let next_state = {
-                    let mut d = p_data_handle.lock().unwrap();
-                    control::read_console_input(cur_state, &mut (*d), &cmd)
+                let line = match &cmd {
+                    ConsoleOutput::Stdout(s) => s,
+                    ConsoleOutput::Stderr(s) => s,
                 };
+                reader_transcript.lock().unwrap().received(line);
 
+                // The commands that follow up on it are sent by `update`
                 {
+                    let mut state = reader_gdb_handle.lock().unwrap();
+
+                    let next_state = {
+                        let mut d = p_data_handle.lock().unwrap();
+                        let next = control::read_console_input(state.clone(), &mut d, &cmd);
+                        if d.report.ready {
+                            write_crash_report(&mut d, Path::new(REPORT_DIR), &report_name());
+                        }
+                        next
+                    };
+
                     gdb_state_hist_console.lock().unwrap().update(&next_state);
-                    *reader_gdb_handle.lock().unwrap() = next_state;
+                    *state = next_state;
                 }
 
-                let cmd_str = match cmd {
-                    ConsoleOutput::Stdout(s) => s,
-                    ConsoleOutput::Stderr(s) => s,
-                };
-
-                match mi::user_output(&cmd_str) {
-                    Some(s) => console_out.push_str(&s),
-                    _ => {}
+                if let Some(ctx) = &*reader_repaint.lock().unwrap() {
+                    ctx.request_repaint();
                 }
 
                 //console_out.push_str(&cmd_str);
             }
         });
 
+        let pty_repaint = repaint.clone();
+        let terminal = terminal::Pty::open(move || {
+            if let Some(ctx) = &*pty_repaint.lock().unwrap() {
+                ctx.request_repaint();
+            }
+        });
+        let terminal = match terminal {
+            Ok(pty) => Some(pty),
+            Err(e) => {
+                println!("Failed to open a terminal for the program: {e}");
+                None
+            }
+        };
+
         let mut input_fields = vec![];
         //input_fields.push("./res/a.out".to_string());
         for _ in 0..20 {
@@ -131,18 +194,185 @@ impl MyApp {
 
         MyApp {
             code: include_str!("./main.rs").into(),
-            user_input: String::new(),
-            console_output: console_handle.clone(),
+            command_line: command_line::CommandLine::load(),
+            transcript,
+            console_view: transcript::ConsoleView::default(),
             sender,
             reader_handle: consume_console_handle,
             gdb_state: gdb_state_handle.clone(),
             input_fields,
+            input_kind: None,
             state_history: gdb_state_hist,
             persistent_data: persistent_data.clone(),
+            repaint,
+            watch_input: String::new(),
+            watch_offer: None,
+            register_view: registers::RegisterView::default(),
+            memory_view: memory::MemoryView::default(),
+            repl_view: repl::ReplView::default(),
+            processes: procs::ProcessList::default(),
+            target: Mutex::new(None),
+            target_input: String::new(),
+            terminal,
+            terminal_view: terminal::TerminalView::default(),
+            session: false,
+            tty_set: false,
         }
     }
 
+    fn transition(&self, next: ControlState) {
+        let next = self.start_target(next);
+        // Only kernels get hardware breakpoints, a new session starts from here
+        match &next {
+            ControlState::TryAttachPort {
+                config: Some(config),
+            } => {
+                self.persistent_data.lock().unwrap().disassembly.hardware =
+                    config.hardware_breakpoints;
+            }
+            ControlState::QemuDialog {
+                config,
+                launch: true,
+            } => {
+                self.persistent_data.lock().unwrap().disassembly.hardware =
+                    config.kernel_config().hardware_breakpoints;
+            }
+            ControlState::GDBNothingLoaded => {
+                self.persistent_data.lock().unwrap().disassembly.hardware = false;
+            }
+            ControlState::RestartAndRecover { .. } => {
+                self.persistent_data.lock().unwrap().gdb_restarted();
+            }
+            _ => {}
+        }
+        self.state_history.lock().unwrap().update(&next);
+        *self.gdb_state.lock().unwrap() = next;
+    }
+
+    /// Starts QEMU or a local gdbserver before we connect to it, if it doesn't start we stay in
+    /// the dialog. GDB only connects once it listens, or it would get an error.
+    fn start_target(&self, next: ControlState) -> ControlState {
+        let (title, program, args, addr) = match &next {
+            ControlState::QemuDialog {
+                config,
+                launch: true,
+            } => (
+                "Serial console",
+                config.qemu.as_str(),
+                config.command_line(),
+                config.host(),
+            ),
+            ControlState::GdbserverDialog {
+                config,
+                launch: true,
+            } if config.local => (
+                "Program output",
+                gdbserver::GDBSERVER,
+                config.command_line(),
+                config.host(),
+            ),
+            _ => return next,
+        };
+
+        let started = procs::Child::spawn(program, &args).and_then(|mut child| {
+            child.wait_for_port(&addr, TARGET_START_TIMEOUT)?;
+            Ok(child)
+        });
+
+        match started {
+            Ok(child) => {
+                *self.target.lock().unwrap() = Some((title, child));
+                next
+            }
+            Err(e) => {
+                self.transcript
+                    .lock()
+                    .unwrap()
+                    .note(&format!("Failed to start {program}: {e}"));
+                match next {
+                    ControlState::QemuDialog { config, .. } => ControlState::QemuDialog {
+                        config,
+                        launch: false,
+                    },
+                    ControlState::GdbserverDialog { config, .. } => ControlState::GdbserverDialog {
+                        config,
+                        launch: false,
+                    },
+                    other => other,
+                }
+            }
+        }
+    }
+
+    /// The watch list is saved on every change, so it's there the next session. Watchpoints go
+    /// through the state machine, so "Reload" sets them again.
+    fn edit_watches(&self, state: &ControlState, edit: watch::WatchEdit) {
+        let watchpoints = !matches!(edit, watch::WatchEdit::Add(_));
+        let cmds = {
+            let mut data = self.persistent_data.lock().unwrap();
+            let cmds = watch::apply(&mut data.watches, edit);
+            if let Err(e) = watch::save(&data.watches) {
+                println!("Failed to save the watches: {e}");
+            }
+            cmds
+        };
+
+        if watchpoints && !cmds.is_empty() {
+            self.transition(ControlState::edit_watchpoints(state, &cmds));
+        } else {
+            self.send_stdin_all(cmds);
+        }
+    }
+
+    fn edit_memory(&self, edit: memory::MemoryEdit) {
+        let cmds = memory::apply(&mut self.persistent_data.lock().unwrap().memory, edit);
+
+        self.send_stdin_all(cmds);
+    }
+
+    fn edit_disassembly(&self, edit: disassembly::DisassemblyEdit) {
+        let cmds = disassembly::apply(&mut self.persistent_data.lock().unwrap().disassembly, edit);
+
+        self.send_stdin_all(cmds);
+    }
+
+    /// Pointers clicked in the REPL open the memory view at their address
+    fn edit_repl(&mut self, edit: repl::ReplEdit) {
+        if let repl::ReplEdit::ShowMemory(addr) = edit {
+            self.memory_view.input = format!("{addr:#x}");
+            self.memory_view.reveal = true;
+            self.edit_memory(memory::MemoryEdit::Read(self.memory_view.input.clone()));
+            return;
+        }
+
+        let cmds = repl::apply(&mut self.persistent_data.lock().unwrap().repl, edit);
+
+        self.send_stdin_all(cmds);
+    }
+
+    /// Sends the commands from a single task, so they reach GDB in order
+    fn send_stdin_all(&self, cmds: Vec<String>) {
+        if cmds.is_empty() {
+            return;
+        }
+
+        {
+            let mut transcript = self.transcript.lock().unwrap();
+            for cmd in &cmds {
+                transcript.sent(cmd);
+            }
+        }
+
+        let tx = self.sender.clone();
+        tokio::spawn(async move {
+            for cmd in cmds {
+                tx.send(InputCommand::StdinInput(cmd)).await.unwrap();
+            }
+        });
+    }
+
     fn send_stdin(&self, input: &str) {
+        self.transcript.lock().unwrap().sent(input);
         let input_owned = input.to_string();
         let tx = self.sender.clone();
         tokio::spawn(async move {
@@ -154,34 +384,68 @@ impl MyApp {
 }
 
 impl eframe::epi::App for MyApp {
+    fn setup(
+        &mut self,
+        ctx: &eframe::egui::Context,
+        _frame: &eframe::epi::Frame,
+        _storage: Option<&dyn eframe::epi::Storage>,
+    ) {
+        *self.repaint.lock().unwrap() = Some(ctx.clone());
+    }
+
     fn update(&mut self, ctx: &eframe::egui::Context, frame: &eframe::epi::Frame) {
         // TODO: create a function to handle this
 
-        let cur_state = { self.gdb_state.lock().unwrap().clone() };
-        let persistent_data = { self.persistent_data.lock().unwrap().clone() };
-        let history = { self.state_history.lock().unwrap().clone() };
+        let (cur_state, cmds) = {
+            let mut state = self.gdb_state.lock().unwrap();
+            let (next_state, cmds) = control::advance_cmds(&state);
+
+            self.state_history.lock().unwrap().update(&state);
+            *state = next_state.clone();
+            (next_state, cmds)
+        };
+        // The session is over. gdbserver outlives the program, "Run again" starts it over there.
+        if cur_state == ControlState::GDBNothingLoaded {
+            *self.target.lock().unwrap() = None;
+            self.session = false;
+            self.persistent_data.lock().unwrap().gdb_restarted();
+
+            // GDB is up, the programs it runs from now on get their own terminal. "Reload" starts
+            // a new GDB that has to be told again.
+            if let Some(pty) = self.terminal.as_ref().filter(|_| !self.tty_set) {
+                control::remember(&pty.command());
+                self.send_stdin(&pty.command());
+                self.tty_set = true;
+            }
+        }
 
-        let (next_state, cmds) = control::advance_cmds(&cur_state);
+        if matches!(
+            cur_state,
+            ControlState::GDBRunning { .. } | ControlState::GDBExited { .. }
+        ) {
+            self.session = true;
+        }
 
+        if let Some((completion, matches)) = self.persistent_data.lock().unwrap().completion.take()
         {
-            self.state_history.lock().unwrap().update(&cur_state);
-            *self.gdb_state.lock().unwrap() = next_state.clone();
+            self.command_line.completed(completion, matches);
         }
 
-        //if &cur_state != &next_state {
-        //    println!("-------------------------------New state!");
-        //    for (i, (label, default)) in next_state.input_fields().iter().enumerate() {
-        //        println!("Overwriting {} with {}", self.input_fields[i], *default);
-        //        self.input_fields[i] = (*default).into();
-        //    }
-        //}
-
-        let cur_state = next_state;
+        let persistent_data = { self.persistent_data.lock().unwrap().clone() };
+        let history = { self.state_history.lock().unwrap().clone() };
 
-        for cmd in cmds {
-            self.send_stdin(&cmd);
+        // Every dialog starts with its own values instead of whatever the last one left behind
+        let kind = std::mem::discriminant(&cur_state);
+        if self.input_kind != Some(kind) {
+            self.input_kind = Some(kind);
+            let values = cur_state.input_values();
+            for (i, field) in self.input_fields.iter_mut().enumerate() {
+                *field = values.get(i).cloned().unwrap_or_default();
+            }
         }
 
+        self.send_stdin_all(cmds);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::TopBottomPanel::top("A")
                 .resizable(true)
@@ -206,8 +470,9 @@ impl eframe::epi::App for MyApp {
                         });
                     }
                     ui.horizontal(|ui| {
-                        for (btn, f) in cur_state.buttons() {
-                            if ui.button(*btn).clicked() {
+                        for (btn, f) in cur_state.buttons_for(&persistent_data) {
+                            let enabled = cur_state.button_enabled(btn);
+                            if ui.add_enabled(enabled, egui::Button::new(btn)).clicked() {
                                 let fields = self
                                     .input_fields
                                     .iter()
@@ -223,16 +488,23 @@ impl eframe::epi::App for MyApp {
                                     )
                                     .collect::<Vec<_>>();
 
-                                let next = f(&cur_state, &fields);
-
-                                self.state_history.lock().unwrap().update(&next);
-                                *self.gdb_state.lock().unwrap() = next;
+                                self.transition(f(&cur_state, &fields));
 
                                 break;
                             }
                         }
                     });
 
+                    ui::architecture_picker(ui, &cur_state, &mut self.input_fields[4]);
+                    if let Some(next) = ui::process_picker(
+                        ui,
+                        &cur_state,
+                        &self.input_fields[0],
+                        &mut self.processes,
+                    ) {
+                        self.transition(next);
+                    }
+
                     ui.separator();
                     ui.collapsing("State history", |ui| {
                         egui::ScrollArea::vertical()
@@ -249,42 +521,80 @@ impl eframe::epi::App for MyApp {
                 });
 
             ui.collapsing("Console", |ui| {
-                let console = {
-                    let data = self.console_output.lock().unwrap().clone();
-                    data
-                };
-
-                egui::ScrollArea::vertical()
-                    .max_height(400.0)
-                    .max_width(f32::INFINITY)
-                    .stick_to_bottom()
-                    .show(ui, |ui| {
-                        ui.monospace(&console);
-                    });
-
-                ui.horizontal(|ui| {
-                    ui.add(
-                        egui::TextEdit::singleline(&mut self.user_input)
-                            .hint_text("Write something")
-                            .font(TextStyle::Monospace),
-                    );
-
-                    if ui.button("Send command").clicked() {
-                        let tx = self.sender.clone();
-                        let input = &self.user_input;
+                // Drawn under the lock, the reader waits for a frame instead of us copying it all
+                ui::console(ui, &self.transcript.lock().unwrap(), &mut self.console_view);
 
-                        {
-                            let mut data = self.console_output.lock().unwrap();
-                            data.push_str(&format!("~> {}\n", input));
-                        };
-
-                        self.send_stdin(input);
-                    }
-                });
+                self.command_line.hardware = persistent_data.disassembly.hardware;
+                if let Some(cmd) = ui::command_line(ui, &mut self.command_line) {
+                    self.send_stdin(&cmd);
+                }
             });
 
-            ui::current_file(ui, &cur_state, &persistent_data);
-            ui::stack_frame(ui, &cur_state, &persistent_data);
+            ui::exit_status(ui, &cur_state);
+            ui::signal(ui, &cur_state, &persistent_data);
+            ui::return_value(ui, &cur_state, &persistent_data);
+            if let Some(next) = ui::current_file(ui, &cur_state, &persistent_data) {
+                self.transition(next);
+            }
+            if let Some(edit) = ui::asm_only(ui, &cur_state, &persistent_data) {
+                self.edit_disassembly(edit);
+            }
+            if let Some(edit) = ui::disassembly(ui, &cur_state, &persistent_data) {
+                self.edit_disassembly(edit);
+            }
+            let mut locked = persistent_data.scheduler_locked;
+            if let Some(next) = ui::threads(ui, &cur_state, &persistent_data, &mut locked) {
+                self.persistent_data.lock().unwrap().scheduler_locked = locked;
+                self.transition(next);
+            }
+            if let Some(next) = ui::stack_frame(ui, &cur_state, &persistent_data) {
+                self.transition(next);
+            }
+            if let Some(next) = ui::variables(ui, &cur_state, &persistent_data) {
+                self.transition(next);
+            }
+            let edit = ui::watches(
+                ui,
+                &cur_state,
+                &persistent_data,
+                &mut self.watch_input,
+                &mut self.watch_offer,
+            );
+            if let Some(edit) = edit {
+                self.edit_watches(&cur_state, edit);
+            }
+            let next = ui::registers(ui, &cur_state, &persistent_data, &mut self.register_view);
+            if let Some(next) = next {
+                self.transition(next);
+            }
+            let edit = ui::repl(ui, &cur_state, &persistent_data, &mut self.repl_view);
+            if let Some(edit) = edit {
+                self.edit_repl(edit);
+            }
+            let edit = ui::memory(ui, &cur_state, &persistent_data, &mut self.memory_view);
+            if let Some(edit) = edit {
+                self.edit_memory(edit);
+            }
+            ui::thread_backtraces(ui, &cur_state, &persistent_data);
+            ui::crash_report(ui, &cur_state, &persistent_data);
+
+            if let Some((title, child)) = &*self.target.lock().unwrap() {
+                let output = child.output.lock().unwrap();
+                let line = ui::target_console(ui, title, &output, &mut self.target_input);
+                drop(output);
+                if let Some(line) = line {
+                    child.send(&line);
+                }
+            }
+
+            if let Some(pty) = &self.terminal {
+                let screen = pty.screen.lock().unwrap();
+                let keys = ui::terminal(ui, self.session, &screen, &mut self.terminal_view);
+                drop(screen);
+                if !keys.is_empty() {
+                    pty.send(&keys);
+                }
+            }
         });
 
         //if buttons.iter().any(|x| *x) {
@@ -297,6 +607,10 @@ impl eframe::epi::App for MyApp {
     fn name(&self) -> &str {
         "Iron Debugger"
     }
+
+    fn on_exit(&mut self) {
+        *self.target.lock().unwrap() = None;
+    }
 }
 
 async fn console(mut rx: mpsc::Receiver<InputCommand>, mut tx: mpsc::Sender<ConsoleOutput>) {
@@ -321,7 +635,10 @@ async fn console(mut rx: mpsc::Receiver<InputCommand>, mut tx: mpsc::Sender<Cons
     let reader = tokio::spawn(async move {
         let mut s = String::new();
 
-        while let Ok(_) = buf_stdout.read_line(&mut s).await {
+        while let Ok(n) = buf_stdout.read_line(&mut s).await {
+            if n == 0 {
+                break;
+            }
             print!("{s}");
             tx.send(ConsoleOutput::Stdout(s.clone())).await.unwrap();
             s.clear();
@@ -332,7 +649,10 @@ async fn console(mut rx: mpsc::Receiver<InputCommand>, mut tx: mpsc::Sender<Cons
     tokio::spawn(async move {
         let mut s = String::new();
 
-        while let Ok(_) = buf_stderr.read_line(&mut s).await {
+        while let Ok(n) = buf_stderr.read_line(&mut s).await {
+            if n == 0 {
+                break;
+            }
             print!("{s}");
             stderr_tx
                 .send(ConsoleOutput::Stderr(s.clone()))
@@ -418,8 +738,139 @@ async fn command_output(cmd: &str) -> String {
     out_val
 }
 
+/// Reports made from the UI go here, relative to where iron-dbg was started
+const REPORT_DIR: &str = "./crash-reports";
+/// Longest we wait for GDB to say something while making a report without the UI
+const REPORT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Longest we wait for a QEMU or a gdbserver we started to listen
+const TARGET_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn report_name() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    format!("crash-{secs}")
+}
+
+/// Writes the report once all of its data came in, and remembers where it went
+fn write_crash_report(data: &mut PersistentData, dir: &Path, name: &str) {
+    if !data.report.ready {
+        return;
+    }
+
+    data.report.ready = false;
+    data.report.written = Some(report::write(data, dir, name).map_err(|e| e.to_string()));
+}
+
+/// Feeds GDB's output to the state machine, the same way the UI does, until `done` says so
+async fn drive(
+    state: &mut ControlState,
+    data: &mut PersistentData,
+    tx: &mpsc::Sender<InputCommand>,
+    rx: &mut mpsc::Receiver<ConsoleOutput>,
+    done: impl Fn(&ControlState, &PersistentData) -> bool,
+) -> Result<(), Box<dyn error::Error>> {
+    loop {
+        // Some states only get to sending their commands after a few steps
+        loop {
+            let (next, cmds) = control::advance_cmds(state);
+            let changed = next != *state;
+            *state = next;
+
+            for c in cmds {
+                tx.send(InputCommand::StdinInput(c)).await?;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        if done(state, data) {
+            return Ok(());
+        }
+
+        match timeout(REPORT_TIMEOUT, rx.recv()).await {
+            Ok(Some(out)) => *state = control::read_console_input(state.clone(), data, &out),
+            Ok(None) => return Err("GDB's console closed".into()),
+            Err(_) => return Err("GDB didn't answer in time".into()),
+        }
+    }
+}
+
+/// `iron-dbg --crash-report <executable> <core dir> [output dir]` writes a report for every core
+/// file in the directory, named after it, without opening the UI
+async fn crash_reports(args: &[String]) -> Result<(), Box<dyn error::Error>> {
+    let (exe, cores, out) = match args {
+        [exe, cores] => (exe, cores, REPORT_DIR),
+        [exe, cores, out] => (exe, cores, out.as_str()),
+        _ => {
+            return Err(
+                "usage: iron-dbg --crash-report <executable> <core dir> [output dir]".into(),
+            )
+        }
+    };
+
+    let mut cores = std::fs::read_dir(cores)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    cores.sort();
+
+    let (tx_cmd, rx_cmd, tx_out, mut rx_out) = create_console_channels();
+    tokio::spawn(console(rx_cmd, tx_out));
+
+    let mut state = ControlState::new();
+    let mut data = PersistentData::default();
+    drive(&mut state, &mut data, &tx_cmd, &mut rx_out, |s, _| {
+        *s == ControlState::GDBNothingLoaded
+    })
+    .await?;
+
+    for core in cores {
+        let name = core
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(report_name);
+
+        data = PersistentData::default();
+        data.report.requested = true;
+        state = ControlState::CoreFileDialog {
+            files: Some((exe.clone(), core.to_string_lossy().into_owned())),
+        };
+
+        let res = drive(&mut state, &mut data, &tx_cmd, &mut rx_out, |_, d| {
+            d.report.ready
+        })
+        .await;
+        if let Err(e) = res {
+            println!("{}: {e}", core.display());
+            continue;
+        }
+
+        write_crash_report(&mut data, Path::new(out), &name);
+        match &data.report.written {
+            Some(Ok(p)) => println!("{}: {}", core.display(), p.display()),
+            Some(Err(e)) => println!("{}: {e}", core.display()),
+            None => {}
+        }
+    }
+
+    tx_cmd
+        .send(InputCommand::StdinInput("-gdb-exit".to_string()))
+        .await?;
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("--crash-report") {
+        return crash_reports(&args[1..]).await;
+    }
+
     let mut options = eframe::NativeOptions::default();
     options.initial_window_size = (Some(Vec2::new(1000., 1000.)));
 
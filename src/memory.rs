@@ -0,0 +1,217 @@
+use crate::mi::escape;
+use crate::tokens::MEMORY;
+
+/// Bytes read at once, the view shows one page at a time
+pub const PAGE_SIZE: u64 = 256;
+pub const ROW_SIZE: usize = 16;
+
+/// The memory shown in the memory view, read again on every stop
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Memory {
+    /// An address or an expression, like `$sp` or `&buf`, evaluated on every read
+    pub expression: Option<String>,
+    pub start: Option<u64>,
+    pub contents: Vec<u8>,
+    /// `contents` at the previous stop, empty if it was another address
+    pub previous: Vec<u8>,
+    pub error: Option<String>,
+}
+
+/// What can be done to the memory view from the UI
+#[derive(Clone, PartialEq, Debug)]
+pub enum MemoryEdit {
+    Read(String),
+    Write(u64, Vec<u8>),
+}
+
+/// What the memory view remembers between frames
+#[derive(Clone, PartialEq, Debug)]
+pub struct MemoryView {
+    pub input: String,
+    /// Bytes per group: 1, 2, 4 or 8
+    pub group: usize,
+    pub little_endian: bool,
+    /// Address of the group being edited and the text typed so far
+    pub editing: Option<(u64, String)>,
+    /// Something else sent us to an address, the panel opens for it
+    pub reveal: bool,
+}
+
+impl Default for MemoryView {
+    fn default() -> MemoryView {
+        MemoryView {
+            input: String::new(),
+            group: 1,
+            little_endian: true,
+            editing: None,
+            reveal: false,
+        }
+    }
+}
+
+impl Memory {
+    pub fn refresh_command(&self) -> Option<String> {
+        self.expression.as_deref().map(read_command)
+    }
+
+    /// Keeps what we have to tell which bytes change until the next stop
+    pub fn stopped(&mut self) {
+        self.previous = self.contents.clone();
+    }
+
+    /// The answer to a read, the bytes from `start` or GDB's error
+    pub fn answered(&mut self, answer: Result<(u64, Vec<u8>), String>) {
+        match answer {
+            Ok((start, contents)) => {
+                if self.start != Some(start) {
+                    self.previous.clear();
+                }
+                self.start = Some(start);
+                self.contents = contents;
+                self.error = None;
+            }
+            Err(e) => {
+                self.contents.clear();
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Whether a write from `=memory-changed` touched what we show
+    pub fn overlaps(&self, addr: u64, len: u64) -> bool {
+        match self.start {
+            Some(start) => addr < start + self.contents.len() as u64 && start < addr + len,
+            None => false,
+        }
+    }
+
+    pub fn changed(&self, offset: usize) -> bool {
+        match self.previous.get(offset) {
+            Some(b) => Some(b) != self.contents.get(offset),
+            None => false,
+        }
+    }
+}
+
+/// Changes what the view shows and returns the commands that go with it
+pub fn apply(memory: &mut Memory, edit: MemoryEdit) -> Vec<String> {
+    match edit {
+        MemoryEdit::Read(expression) => {
+            let expression = expression.trim().to_string();
+            if expression.is_empty() {
+                return vec![];
+            }
+            memory.expression = Some(expression);
+            memory.refresh_command().into_iter().collect()
+        }
+        MemoryEdit::Write(addr, bytes) => {
+            let mut cmds = vec![format!(
+                "-data-write-memory-bytes {addr:#x} {}",
+                to_hex(&bytes)
+            )];
+            cmds.extend(memory.refresh_command());
+            cmds
+        }
+    }
+}
+
+fn read_command(expression: &str) -> String {
+    format!(
+        "{MEMORY}-data-read-memory-bytes \"{}\" {PAGE_SIZE}",
+        escape(expression)
+    )
+}
+
+pub fn is_memory_token(token: Option<u64>) -> bool {
+    token == Some(MEMORY)
+}
+
+/// `contents` of `-data-read-memory-bytes`, two hex digits per byte
+pub fn parse_contents(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A group of bytes as one number, the most significant byte first
+pub fn group_text(bytes: &[u8], little_endian: bool) -> String {
+    if little_endian {
+        to_hex(&bytes.iter().rev().copied().collect::<Vec<_>>())
+    } else {
+        to_hex(bytes)
+    }
+}
+
+/// What the user typed for a group, in the same order `group_text` shows it, as the bytes to write
+pub fn parse_group(input: &str, size: usize, little_endian: bool) -> Option<Vec<u8>> {
+    let digits = input.trim();
+    let digits = digits.strip_prefix("0x").unwrap_or(digits);
+    if digits.is_empty() || digits.len() > size * 2 {
+        return None;
+    }
+
+    let mut bytes = parse_contents(&format!("{digits:0>width$}", width = size * 2))?;
+    if little_endian {
+        bytes.reverse();
+    }
+
+    Some(bytes)
+}
+
+pub fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| {
+            if b.is_ascii_graphic() || *b == b' ' {
+                *b as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut memory = Memory::default();
+
+        let cmds = apply(&mut memory, MemoryEdit::Read(" &buf ".to_string()));
+        assert_eq!(cmds, vec![r#"3-data-read-memory-bytes "&buf" 256"#]);
+
+        memory.answered(Ok((0x1000, vec![1, 2, 3, 4])));
+        memory.stopped();
+        memory.answered(Ok((0x1000, vec![1, 9, 3, 4])));
+        assert!(!memory.changed(0));
+        assert!(memory.changed(1));
+        assert!(memory.overlaps(0x1003, 4));
+        assert!(!memory.overlaps(0x1004, 4));
+
+        // Another address, nothing to compare with
+        memory.answered(Ok((0x2000, vec![1, 2, 3, 4])));
+        assert!(!memory.changed(1));
+
+        let cmds = apply(&mut memory, MemoryEdit::Write(0x2001, vec![0xff, 0x0a]));
+        assert_eq!(cmds[0], "-data-write-memory-bytes 0x2001 ff0a");
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(parse_contents("00ff7f"), Some(vec![0, 0xff, 0x7f]));
+        assert_eq!(parse_contents("0g"), None);
+        assert_eq!(group_text(&[0x78, 0x56, 0x34, 0x12], true), "12345678");
+        assert_eq!(group_text(&[0x78, 0x56, 0x34, 0x12], false), "78563412");
+        assert_eq!(parse_group("1234", 4, true), Some(vec![0x34, 0x12, 0, 0]));
+        assert_eq!(parse_group("0x1234", 2, false), Some(vec![0x12, 0x34]));
+        assert_eq!(parse_group("123", 1, true), None);
+        assert_eq!(ascii(b"hi\n\x00!"), "hi..!");
+    }
+}
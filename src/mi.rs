@@ -1,12 +1,12 @@
 use anyhow::anyhow;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_till, take_while};
-use nom::character::complete::char;
+use nom::character::complete::{char, digit0, digit1};
 use nom::character::is_digit;
 use nom::combinator::{map, map_res, opt};
 use nom::error::ErrorKind;
 use nom::multi::{many0, many_till};
-use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use nom::{Err, IResult, Parser};
 use snailquote::unescape;
 
@@ -121,8 +121,27 @@ pub enum Output {
     ResultRecord(MIResult, Option<mi_parse::MIRepr>),
 }
 
+/// Commands can be prefixed by a number, GDB repeats it in front of the result record of that
+/// command. e.g. `7-thread-list-ids` is answered with `7^done,...`
+pub fn token(input: &str) -> Option<u64> {
+    let (rest, digits) = digit1::<_, nom::error::Error<&str>>(input).ok()?;
+
+    if rest.starts_with('^') {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Quotes `s` for an MI command argument, it goes between double quotes
+pub fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 pub fn parse_stream(input: &str) -> IResult<&str, Output> {
     use nom::combinator::map;
+    // The token is only useful to the ones that sent it, see `token`
+    let (input, _) = digit0(input)?;
     let (rest, out) = alt((
         map(
             preceded(
@@ -193,7 +212,7 @@ fn mi_result(input: &str) -> IResult<&str, MIResult> {
             Ok((
                 rest,
                 MIResult::Error {
-                    msg: msg.to_string(),
+                    msg: mi_parse::unescape(msg),
                     code: code.and_then(|s| Some(s.to_string())),
                 },
             ))
@@ -216,24 +235,7 @@ fn owned_unescaped(input: &str) -> IResult<&str, String> {
 }
 
 fn c_str(input: &str) -> IResult<&str, &str> {
-    let (rest, s) = delimited(char('\"'), take_while(|c| c != '\"'), char('\"'))(input)?;
-
-    Ok((rest, s))
-}
-
-pub fn user_output(src: &str) -> Option<String> {
-    let p = parse_stream(src);
-
-    if let Ok((_, Output::ConsoleStream(src))) = p {
-        let src = if src.ends_with('\n') {
-            format!("{}\n", src.trim_end())
-        } else {
-            src
-        };
-        Some(src)
-    } else {
-        None
-    }
+    mi_parse::quoted(input)
 }
 
 #[cfg(test)]
@@ -254,6 +256,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            parse_stream(r#"^error,msg="No symbol \"foo\" in current context.""#)
+                .unwrap()
+                .1,
+            Output::ResultRecord(
+                MIResult::Error {
+                    msg: "No symbol \"foo\" in current context.".to_string(),
+                    code: None
+                },
+                Some(mi_parse::MIRepr::Map(
+                    [(
+                        "msg".to_string(),
+                        mi_parse::MIRepr::Literal("No symbol \"foo\" in current context.".into())
+                    )]
+                    .into_iter()
+                    .collect()
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_token() {
+        assert_eq!(token(r#"7^done,thread-ids={}"#), Some(7));
+        assert_eq!(token(r#"^done"#), None);
+        assert_eq!(token(r#"*stopped,reason="end-stepping-range""#), None);
+        assert_eq!(
+            parse_stream("42^done").unwrap().1,
+            Output::ResultRecord(MIResult::Done, None)
+        );
+    }
+
     #[test]
     fn test_parse() {
         assert_eq!(
@@ -283,13 +319,13 @@ mod tests {
     #[test]
     fn test_from_mi_macro() {
         let f = Frame {
-            //addr: 42,
+            addr: Some(42),
             func: "jjjj".to_string(),
             args: Some(vec![]),
-            //file: "sla".to_string(),
-            //fullname: "idk".to_string(),
-            //line: 69,
-            //arch: "aaa".to_string(),
+            file: Some("sla".to_string()),
+            fullname: Some("/idk/sla".to_string()),
+            line: Some(69),
+            arch: Some("i386:x86-64".to_string()),
             level: 5,
         };
 
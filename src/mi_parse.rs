@@ -1,6 +1,7 @@
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take_till, take_till1, take_while, take_while1};
-use nom::character::complete::char;
+use nom::bytes::complete::{escaped, is_not, take_while1};
+use nom::character::complete::{anychar, char};
+use nom::combinator::opt;
 use nom::multi::{separated_list0, separated_list1};
 use nom::sequence::{delimited, pair, terminated};
 use nom::IResult;
@@ -46,16 +47,32 @@ pub fn mi_repr(input: &str) -> IResult<&str, MIRepr> {
 
 fn name(input: &str) -> IResult<&str, String> {
     let (rest, lit) = terminated(
-        take_while1(|c: char| c != '=' && (c.is_alphanumeric() || c == '-')),
+        take_while1(|c: char| c != '=' && (c.is_alphanumeric() || c == '-' || c == '_')),
         char('='),
     )(input)?;
 
     Ok((rest, lit.into()))
 }
 
+/// The contents of a C string, escaped quotes such as in `value="0x4006f4 \"hello\""` included
+pub fn quoted(input: &str) -> IResult<&str, &str> {
+    let (rest, lit) = delimited(
+        char('\"'),
+        opt(escaped(is_not("\\\""), '\\', anychar)),
+        char('\"'),
+    )(input)?;
+
+    Ok((rest, lit.unwrap_or("")))
+}
+
+/// GDB escapes strings like C does, if we can't make sense of an escape we keep it as it is
+pub fn unescape(lit: &str) -> String {
+    snailquote::unescape(&format!("\"{lit}\"")).unwrap_or_else(|_| lit.to_string())
+}
+
 fn literal(input: &str) -> IResult<&str, MIRepr> {
-    let (rest, lit) = delimited(char('\"'), take_till(|c: char| c == '\"'), char('\"'))(input)?;
-    Ok((rest, MIRepr::Literal(lit.into())))
+    let (rest, lit) = quoted(input)?;
+    Ok((rest, MIRepr::Literal(unescape(lit))))
 }
 
 fn map(input: &str) -> IResult<&str, MIRepr> {
@@ -63,9 +80,10 @@ fn map(input: &str) -> IResult<&str, MIRepr> {
 
     let (rest, v) = alt((
         separated_list1(char(','), pair(name, alt((map, array, literal)))),
+        // `thread-ids={}` when there are no threads
         delimited(
             char('{'),
-            separated_list1(char(','), pair(name, alt((map, array, literal)))),
+            separated_list0(char(','), pair(name, alt((map, array, literal)))),
             char('}'),
         ),
     ))(input)?;
@@ -119,6 +137,40 @@ mod tests {
         assert_eq!(v, MIRepr::Literal("breakpoint-hit".into()));
     }
 
+    #[test]
+    fn test_escaped_literal() {
+        let (rest, v) = literal(r#""0x4006f4 \"hello\"",name="s""#).unwrap();
+        assert_eq!(v, MIRepr::Literal("0x4006f4 \"hello\"".into()));
+        assert_eq!(rest, r#",name="s""#);
+
+        let (rest, v) = literal(r#""""#).unwrap();
+        assert_eq!(v, MIRepr::Literal("".into()));
+        assert_eq!(rest, "");
+
+        let (_, v) =
+            mi_repr(r#"variables=[{name="c",value="10 '\\n'"},{name="p",value="\"\""}]"#).unwrap();
+        let values = match v {
+            MIRepr::Map(mut m) => match m.remove("variables") {
+                Some(MIRepr::Array(vars)) => vars
+                    .into_iter()
+                    .map(|var| match var {
+                        MIRepr::Map(mut var) => var.remove("value"),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+                other => panic!("{other:?}"),
+            },
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            values,
+            vec![
+                Some(MIRepr::Literal("10 '\\n'".into())),
+                Some(MIRepr::Literal("\"\"".into()))
+            ]
+        );
+    }
+
     #[test]
     fn test_map_array() {
         let (rest, v) = map(r#"reason="breakpoint-hit",line="4""#).unwrap();
@@ -134,6 +186,9 @@ mod tests {
 
         let (rest, v) = map(r#"thread-id="all""#).unwrap();
         println!("{v:?}, rest {rest}");
+
+        let (rest, _) = map(r#"thread-ids={},number-of-threads="0""#).unwrap();
+        assert_eq!(rest, "");
     }
 
     #[test]
@@ -26,7 +26,7 @@ pub enum AsyncStateStatus {
     },
     Stopped {
         reason: StoppedReason,
-        frame: Option<Frame>,
+        frame: Option<Box<Frame>>,
         thread: String,
         stopped_threads: String,
         core: String,
@@ -145,23 +145,88 @@ pub enum StoppedReason {
     Exec,
 }
 
+/// How the inferior went away, taken from `*stopped,reason="exited..."`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitStatus {
+    Normal,
+    Code(String),
+    Signal { name: String, meaning: String },
+}
+
+/// The signal that stopped or killed the inferior, e.g. `SIGSEGV` and `Segmentation fault`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub name: String,
+    pub meaning: String,
+}
+
+/// One entry of `-stack-list-variables`, `value` is missing for structs with `--simple-values`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub var_type: Option<String>,
+    pub value: Option<String>,
+    pub arg: bool,
+}
+
+/// A GDB variable object as told by `-var-create` and `-var-list-children`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarObj {
+    pub name: String,
+    /// Only children have it, it's the field name or the array index
+    pub expression: Option<String>,
+    pub var_type: Option<String>,
+    pub value: Option<String>,
+    pub numchild: u32,
+}
+
+/// One entry of the `changelist` of `-var-update`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarChange {
+    pub name: String,
+    pub value: Option<String>,
+    /// `true`, `false` when its frame is gone, or `invalid` when it can't be used anymore
+    pub in_scope: String,
+    pub new_type: Option<String>,
+    pub new_numchild: Option<u32>,
+}
+
 /// [docs](https://sourceware.org/gdb/onlinedocs/gdb/GDB_002fMI-Frame-Information.html#GDB_002fMI-Frame-Information)
 #[derive(Debug, Clone, PartialEq, FromMI)]
 #[name = "frame"]
 pub struct Frame {
-    // #[name = "addr"]
-    // pub addr: u64,
+    #[name = "addr"]
+    pub addr: Option<u64>,
     #[name = "func"]
     pub func: String,
+    /// Name, type and value, as in `-stack-list-arguments --simple-values`
     pub args: Option<Vec<(String, String, String)>>,
-    //pub file: String,
-    //pub fullname: String,
-    //pub line: u32,
-    //pub arch: String,
+    /// Missing when there's no debug info
+    #[name = "file"]
+    pub file: Option<String>,
+    /// Absolute path of `file`, this is the one we can open
+    #[name = "fullname"]
+    pub fullname: Option<String>,
+    #[name = "line"]
+    pub line: Option<u32>,
+    #[name = "arch"]
+    pub arch: Option<String>,
     /// GDB's docs say this field is present, but I don't see it.
     pub level: u32,
 }
 
+/// One line of `-data-disassemble`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub address: u64,
+    pub func: Option<String>,
+    pub offset: Option<u32>,
+    pub inst: String,
+    /// The source line it was compiled from, only in mixed mode and with debug info
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
 /// [docs](https://sourceware.org/gdb/onlinedocs/gdb/GDB_002fMI-Breakpoint-Information.html#GDB_002fMI-Breakpoint-Information)
 #[derive(Debug, Clone, PartialEq)]
 pub struct Breakpoint {
@@ -178,5 +243,12 @@ pub struct Breakpoint {
     times: String,
 }
 
-//Thread docs
-// https://sourceware.org/gdb/onlinedocs/gdb/GDB_002fMI-Thread-Information.html#GDB_002fMI-Thread-Information
+/// [docs](https://sourceware.org/gdb/onlinedocs/gdb/GDB_002fMI-Thread-Information.html#GDB_002fMI-Thread-Information)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thread {
+    pub id: String,
+    pub target_id: String,
+    pub name: Option<String>,
+    pub state: String,
+    pub frame: Option<Frame>,
+}
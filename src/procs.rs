@@ -0,0 +1,375 @@
+use std::fs;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process;
+use tokio::sync::mpsc;
+
+/// A local process we can attach to, read from `/proc`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Process {
+    pub pid: u32,
+    pub cmdline: String,
+    pub user: String,
+}
+
+impl Process {
+    /// Case insensitive match against the pid, the command line and the user
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+
+        filter.is_empty()
+            || self.pid.to_string().contains(&filter)
+            || self.cmdline.to_lowercase().contains(&filter)
+            || self.user.to_lowercase().contains(&filter)
+    }
+}
+
+/// How long the picker shows a list before reading `/proc` again
+const LIST_AGE: Duration = Duration::from_secs(2);
+
+/// `list` reads all of `/proc`, so the picker keeps what it got for a while
+#[derive(Clone, Debug, Default)]
+pub struct ProcessList {
+    pub processes: Vec<Process>,
+    listed: Option<Instant>,
+}
+
+impl ProcessList {
+    /// Lists the processes again if `now` is set or the list is older than `LIST_AGE`
+    pub fn refresh(&mut self, now: bool) {
+        if now || self.listed.is_none_or(|t| t.elapsed() >= LIST_AGE) {
+            self.processes = list();
+            self.listed = Some(Instant::now());
+        }
+    }
+}
+
+pub fn list() -> Vec<Process> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+
+    let mut procs = match fs::read_dir("/proc") {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| process(pid, &passwd))
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+
+    procs.sort_by_key(|p| p.pid);
+    procs
+}
+
+/// `None` when the process went away while we were reading it
+fn process(pid: u32, passwd: &str) -> Option<Process> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let cmdline = fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+
+    // Kernel threads have no command line, their name is in the status file
+    let cmdline = match parse_cmdline(&cmdline) {
+        s if s.is_empty() => format!("[{}]", status_field(&status, "Name")?),
+        s => s,
+    };
+
+    let uid = status_field(&status, "Uid")?
+        .split_whitespace()
+        .next()?
+        .parse::<u32>()
+        .ok()?;
+
+    Some(Process {
+        pid,
+        cmdline,
+        user: user_name(passwd, uid).unwrap_or_else(|| uid.to_string()),
+    })
+}
+
+/// The arguments in `/proc/<pid>/cmdline` are separated by NUL bytes
+fn parse_cmdline(raw: &[u8]) -> String {
+    raw.split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn status_field<'a>(status: &'a str, name: &str) -> Option<&'a str> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .map(|v| v.trim())
+}
+
+fn user_name(passwd: &str, uid: u32) -> Option<String> {
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id = fields.nth(1)?.parse::<u32>().ok()?;
+
+        if id == uid {
+            Some(name.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Yama's `ptrace_scope` is the usual reason attaching fails for a process we can see
+fn ptrace_scope() -> Option<u32> {
+    fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Explains GDB's `ptrace: Operation not permitted.` in terms of what the user can change
+pub fn describe_attach_error(msg: &str) -> String {
+    if !msg.contains("ptrace") {
+        return msg.to_string();
+    }
+
+    let hint = match ptrace_scope() {
+        Some(1) => "kernel.yama.ptrace_scope is 1, only descendants of GDB can be traced. \
+                    Run `sudo sysctl kernel.yama.ptrace_scope=0` or run iron-dbg as root."
+            .to_string(),
+        Some(2) => "kernel.yama.ptrace_scope is 2, only processes with CAP_SYS_PTRACE can trace. \
+                    Run iron-dbg as root."
+            .to_string(),
+        Some(3) => "kernel.yama.ptrace_scope is 3, attaching is disabled until the next reboot."
+            .to_string(),
+        _ => "Check that the process belongs to you and isn't already being traced.".to_string(),
+    };
+
+    format!("{msg}\n{hint}")
+}
+
+/// Splits arguments as a shell would: single quotes keep everything, double quotes keep all but the
+/// escapes `\"` and `\\`, and a backslash outside quotes escapes the next character
+pub fn split_args(src: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg: Option<String> = None;
+    let mut chars = src.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                arg.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => arg.extend(['\\', c]),
+                            None => arg.push('\\'),
+                        },
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '\\' => arg.get_or_insert_with(String::new).extend(chars.next()),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    args.extend(arg);
+    args
+}
+
+/// How much of the console output of a `Child` is kept, a kernel printing all the time would fill
+/// the memory
+const MAX_OUTPUT: usize = 256 * 1024;
+
+/// A QEMU or a gdbserver we started, it's killed when this is dropped
+pub struct Child {
+    child: process::Child,
+    /// What came out of its console lately, at most `MAX_OUTPUT` bytes
+    pub output: Arc<Mutex<String>>,
+    input: mpsc::Sender<String>,
+}
+
+impl Child {
+    pub fn spawn(program: &str, args: &[String]) -> std::io::Result<Child> {
+        let mut child = process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let output = Arc::new(Mutex::new(String::new()));
+
+        if let Some(stdout) = child.stdout.take() {
+            forward(stdout, output.clone(), true);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward(stderr, output.clone(), false);
+        }
+
+        let (input, mut rx) = mpsc::channel::<String>(16);
+        if let Some(mut stdin) = child.stdin.take() {
+            tokio::spawn(async move {
+                while let Some(line) = rx.recv().await {
+                    if stdin.write_all(line.as_bytes()).await.is_err() {
+                        break;
+                    }
+                    let _ = stdin.flush().await;
+                }
+            });
+        }
+
+        Ok(Child {
+            child,
+            output,
+            input,
+        })
+    }
+
+    /// Waits until something listens on `addr`, GDB is told to connect right after. Fails when
+    /// the process is gone or `timeout` passes first.
+    pub fn wait_for_port(&mut self, addr: &str, timeout: Duration) -> std::io::Result<()> {
+        let start = Instant::now();
+
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(std::io::Error::other(format!("it exited with {status}")));
+            }
+
+            match std::net::TcpStream::connect(addr) {
+                Ok(_) => return Ok(()),
+                Err(e) if start.elapsed() >= timeout => return Err(e),
+                Err(_) => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+    }
+
+    /// Types `line` into its console
+    pub fn send(&self, line: &str) {
+        let _ = self.input.try_send(format!("{line}\n"));
+    }
+}
+
+/// The console doesn't end its prompts with a new line, so it's read as it comes. Only one of the
+/// streams says when the process is gone.
+fn forward(
+    mut out: impl AsyncRead + Unpin + Send + 'static,
+    output: Arc<Mutex<String>>,
+    says_gone: bool,
+) {
+    tokio::spawn(async move {
+        let mut buf = [0; 1024];
+        while let Ok(n) = out.read(&mut buf).await {
+            if n == 0 {
+                break;
+            }
+            let s = String::from_utf8_lossy(&buf[..n]).replace('\r', "");
+            push_output(&mut output.lock().unwrap(), &s);
+        }
+        if says_gone {
+            push_output(&mut output.lock().unwrap(), "\n[The process is gone]\n");
+        }
+    });
+}
+
+/// Drops the oldest lines once there's more than `MAX_OUTPUT`
+fn push_output(output: &mut String, s: &str) {
+    output.push_str(s);
+
+    if output.len() <= MAX_OUTPUT {
+        return;
+    }
+
+    let mut cut = output.len() - MAX_OUTPUT;
+    while !output.is_char_boundary(cut) {
+        cut += 1;
+    }
+    if let Some(i) = output[cut..].find('\n') {
+        cut += i + 1;
+    }
+    output.drain(..cut);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc() {
+        assert_eq!(
+            parse_cmdline(b"./a.out\0--verbose\0x\0"),
+            "./a.out --verbose x"
+        );
+        assert_eq!(parse_cmdline(b""), "");
+
+        let status = "Name:\tbash\nUmask:\t0022\nUid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(status_field(status, "Name"), Some("bash"));
+        assert_eq!(status_field(status, "Uid"), Some("1000\t1000\t1000\t1000"));
+
+        let passwd = "root:x:0:0:root:/root:/bin/bash\ngbrls:x:1000:1000::/home/gbrls:/bin/zsh\n";
+        assert_eq!(user_name(passwd, 1000), Some("gbrls".to_string()));
+        assert_eq!(user_name(passwd, 42), None);
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"-append "console=ttyS0 root=/dev/sda" -m 1G"#),
+            vec!["-append", "console=ttyS0 root=/dev/sda", "-m", "1G"]
+        );
+        assert_eq!(
+            split_args(r#"  'a "b"' c\ d "e \"f\" \g" "" "#),
+            vec!["a \"b\"", "c d", "e \"f\" \\g", ""]
+        );
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn test_push_output() {
+        let mut output = String::new();
+        let line = format!("{}\n", "é".repeat(99));
+        for _ in 0..MAX_OUTPUT / line.len() + 10 {
+            push_output(&mut output, &line);
+        }
+
+        assert!(output.len() <= MAX_OUTPUT);
+        assert!(output.starts_with('é') && output.ends_with('\n'));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let mut child = Child::spawn("sleep", &["5".to_string()]).unwrap();
+        assert!(child.wait_for_port(&addr, Duration::from_secs(1)).is_ok());
+
+        // Nothing listens and it's gone, we don't wait for the timeout
+        drop(listener);
+        let mut child = Child::spawn("true", &[]).unwrap();
+        let start = Instant::now();
+        assert!(child.wait_for_port(&addr, Duration::from_secs(5)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_matches() {
+        let p = Process {
+            pid: 4242,
+            cmdline: "qemu-system-x86_64 -s -S".to_string(),
+            user: "gbrls".to_string(),
+        };
+
+        assert!(p.matches(""));
+        assert!(p.matches("QEMU"));
+        assert!(p.matches("424"));
+        assert!(p.matches("gbr"));
+        assert!(!p.matches("gdb"));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::config::{self, field_or, KeyValues};
+use crate::kernel::KernelConfig;
+use crate::procs::split_args;
+
+/// How QEMU is started for kernel debugging. Saved so the next session starts the same way.
+#[derive(Clone, PartialEq, Debug)]
+pub struct QemuConfig {
+    /// The `qemu-system-*` binary
+    pub qemu: String,
+    pub kernel: String,
+    pub initrd: String,
+    /// Anything else for QEMU, split as a shell would
+    pub args: String,
+    /// Where QEMU's gdbstub listens
+    pub port: String,
+}
+
+impl Default for QemuConfig {
+    fn default() -> QemuConfig {
+        QemuConfig {
+            qemu: "qemu-system-x86_64".to_string(),
+            kernel: String::new(),
+            initrd: String::new(),
+            args: String::new(),
+            port: "1234".to_string(),
+        }
+    }
+}
+
+impl QemuConfig {
+    /// The saved configuration, or the default one if there's none
+    pub fn load() -> QemuConfig {
+        config::load("qemu", QemuConfig::default())
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        config::save("qemu", self)
+    }
+
+    /// Same order as the fields of `ControlState::QemuDialog`, empty ones keep the defaults
+    pub fn from_fields(fields: &[String]) -> QemuConfig {
+        let default = QemuConfig::default();
+
+        QemuConfig {
+            qemu: field_or(&fields[0], default.qemu),
+            kernel: fields[1].trim().to_string(),
+            initrd: fields[2].trim().to_string(),
+            args: fields[3].trim().to_string(),
+            port: field_or(&fields[4], default.port),
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.qemu.clone(),
+            self.kernel.clone(),
+            self.initrd.clone(),
+            self.args.clone(),
+            self.port.clone(),
+        ]
+    }
+
+    /// QEMU waits for GDB before running anything, and the first serial port goes to its stdio
+    pub fn command_line(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if !self.kernel.is_empty() {
+            args.extend(["-kernel".to_string(), self.kernel.clone()]);
+        }
+        if !self.initrd.is_empty() {
+            args.extend(["-initrd".to_string(), self.initrd.clone()]);
+        }
+
+        args.extend(
+            [
+                "-gdb",
+                &format!("tcp::{}", self.port),
+                "-S",
+                "-display",
+                "none",
+                "-monitor",
+                "none",
+                "-serial",
+                "stdio",
+            ]
+            .map(String::from),
+        );
+        args.extend(split_args(&self.args));
+
+        args
+    }
+
+    pub fn host(&self) -> String {
+        format!("localhost:{}", self.port)
+    }
+
+    /// The symbols, modules and architecture saved by the gdbstub dialog, connecting to our QEMU
+    pub fn kernel_config(&self) -> KernelConfig {
+        KernelConfig {
+            host: self.host(),
+            ..KernelConfig::load()
+        }
+    }
+}
+
+impl KeyValues for QemuConfig {
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("qemu", self.qemu.clone()),
+            ("kernel", self.kernel.clone()),
+            ("initrd", self.initrd.clone()),
+            ("args", self.args.clone()),
+            ("port", self.port.clone()),
+        ]
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let field = match key {
+            "qemu" => &mut self.qemu,
+            "kernel" => &mut self.kernel,
+            "initrd" => &mut self.initrd,
+            "args" => &mut self.args,
+            "port" => &mut self.port,
+            _ => return,
+        };
+        *field = value.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let fields = vec![
+            "".to_string(),
+            "arch/x86/boot/bzImage".to_string(),
+            "".to_string(),
+            "-append \"console=ttyS0 root=/dev/sda\" -m 1G".to_string(),
+            "".to_string(),
+        ];
+        let config = QemuConfig::from_fields(&fields);
+
+        assert_eq!(config.qemu, "qemu-system-x86_64");
+        assert_eq!(config.host(), "localhost:1234");
+        assert_eq!(
+            config.command_line(),
+            vec![
+                "-kernel",
+                "arch/x86/boot/bzImage",
+                "-gdb",
+                "tcp::1234",
+                "-S",
+                "-display",
+                "none",
+                "-monitor",
+                "none",
+                "-serial",
+                "stdio",
+                "-append",
+                "console=ttyS0 root=/dev/sda",
+                "-m",
+                "1G",
+            ]
+        );
+        assert_eq!(
+            config::parse(QemuConfig::default(), &config::serialize(&config)),
+            config
+        );
+    }
+}
@@ -56,17 +56,25 @@ pub fn current_file(input: &mi::Output) -> Option<PathBuf> {
     get(&repr, line_path).and_then(|x| Some(PathBuf::from(x.to_string())))
 }
 
-pub fn frames_from_repr(repr: &MIRepr) -> Option<Vec<mi_types::Frame>> {
-    get(&repr, &["stack"]).and_then(|repr| match repr {
-        MIRepr::Array(v) => match &v[0] {
-            MIRepr::Array(v) => Some(
-                v.into_iter()
-                    .map(|x| frame_from_repr(&x).unwrap())
-                    .collect(),
-            ),
-            _ => None,
+/// `frame={...},frame={...}` is parsed as an array of maps, but a single frame is parsed as a map,
+/// there's no repeated key to tell it's an array. The same goes for `child={...}`
+fn frame_list(repr: &MIRepr) -> Vec<&MIRepr> {
+    match repr {
+        MIRepr::Array(v) => match v.first() {
+            Some(MIRepr::Array(v)) => v.iter().collect(),
+            Some(frame @ MIRepr::Map(_)) => vec![frame],
+            _ => vec![],
         },
-        _ => None,
+        _ => vec![],
+    }
+}
+
+pub fn frames_from_repr(repr: &MIRepr) -> Option<Vec<mi_types::Frame>> {
+    get(repr, &["stack"]).map(|repr| {
+        frame_list(&repr)
+            .into_iter()
+            .filter_map(frame_from_repr)
+            .collect()
     })
 }
 
@@ -83,24 +91,148 @@ pub fn frames(input: &mi::Output) -> Option<Vec<mi_types::Frame>> {
 /// Querying the output of -stack-list-frames
 fn frame_from_repr(repr: &MIRepr) -> Option<mi_types::Frame> {
     get(&repr, &["frame"]).and_then(|frame| {
-        let func = get(&frame, &["func"]).unwrap().to_string();
-        let level = get(&frame, &["level"]).unwrap().to_u32();
+        // Frames without debug info may lack these
+        let func = get(&frame, &["func"])
+            .map(|x| x.to_string())
+            .unwrap_or_else(|| "??".to_string());
+        let level = get(&frame, &["level"]).map(|x| x.to_u32()).unwrap_or(0);
 
         Some(mi_types::Frame {
+            addr: get(&frame, &["addr"]).and_then(|x| parse_address(&x.to_string())),
             func,
             level,
             args: None,
+            file: get(&frame, &["file"]).map(|x| x.to_string()),
+            fullname: get(&frame, &["fullname"]).map(|x| x.to_string()),
+            line: get(&frame, &["line"]).map(|x| x.to_u32()),
+            arch: get(&frame, &["arch"]).map(|x| x.to_string()),
         })
     })
 }
 
-/// Querying the output of -stack-list-arguments 2
-pub fn frame_args_from_repr(repr: &MIRepr) -> Option<Vec<(String, String, String)>> {
-    get(&repr, &["stack-args"]).and_then(|frames| {
-        match frames {
-            MIRepr::Array(f) => None,
+/// GDB writes addresses as `0x000000000040114f`
+pub fn parse_address(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// Arguments of every frame by level, each one as name, type and value
+pub type FrameArgs = Vec<(u32, Vec<(String, String, String)>)>;
+
+/// Querying the output of -stack-list-arguments 2, the arguments of every frame by level
+pub fn frame_args_from_repr(repr: &MIRepr) -> Option<FrameArgs> {
+    get(repr, &["stack-args"]).map(|frames| {
+        frame_list(&frames)
+            .into_iter()
+            .filter_map(|f| {
+                let frame = get(f, &["frame"])?;
+                let level = get(&frame, &["level"])?.to_u32();
+                let args = match get(&frame, &["args"]) {
+                    Some(MIRepr::Array(args)) => args
+                        .iter()
+                        .filter_map(|a| {
+                            Some((
+                                get(a, &["name"])?.to_string(),
+                                get(a, &["type"]).map(|x| x.to_string()).unwrap_or_default(),
+                                get(a, &["value"])
+                                    .map(|x| x.to_string())
+                                    .unwrap_or_default(),
+                            ))
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+
+                Some((level, args))
+            })
+            .collect()
+    })
+}
+
+pub fn frame_args(input: &mi::Output) -> Option<FrameArgs> {
+    frame_args_from_repr(&mi_repr(input)?)
+}
+
+/// Querying the output of -thread-list-ids
+pub fn thread_ids(input: &mi::Output) -> Option<Vec<String>> {
+    let ids = get(&mi_repr(input)?, &["thread-ids"])?;
+
+    // Same as frames, one thread is a map and more than one are an array of maps
+    let ids = match ids {
+        MIRepr::Array(v) => v.iter().filter_map(|x| get(x, &["thread-id"])).collect(),
+        MIRepr::Map(_) => get(&ids, &["thread-id"]).into_iter().collect(),
+        _ => vec![],
+    };
+
+    Some(ids.into_iter().map(|x| x.to_string()).collect())
+}
+
+/// Querying the output of -data-list-register-names, unused numbers have empty names
+pub fn register_names(input: &mi::Output) -> Option<Vec<String>> {
+    match get(&mi_repr(input)?, &["register-names"])? {
+        MIRepr::Array(v) => Some(v.iter().map(|x| x.to_string()).collect()),
+        _ => None,
+    }
+}
+
+/// Querying the output of -data-list-register-values, pairs of register number and value
+pub fn register_values(input: &mi::Output) -> Option<Vec<(u32, String)>> {
+    match get(&mi_repr(input)?, &["register-values"])? {
+        MIRepr::Array(v) => Some(
+            v.iter()
+                .filter_map(|r| {
+                    Some((
+                        get(r, &["number"])?.to_u32(),
+                        get(r, &["value"])?.to_string(),
+                    ))
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Querying the output of -data-disassemble. In mixed mode the instructions come grouped by
+/// source line, unless there's no line information for them.
+pub fn instructions(input: &mi::Output) -> Option<Vec<mi_types::Instruction>> {
+    let insns = get(&mi_repr(input)?, &["asm_insns"])?;
+
+    let lines = frame_list(&insns)
+        .into_iter()
+        .filter_map(|l| get(l, &["src_and_asm_line"]))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return match insns {
+            MIRepr::Array(v) => Some(v.iter().filter_map(instruction_from_repr).collect()),
             _ => None,
+        };
+    }
+
+    let mut instructions = vec![];
+    for l in lines {
+        let file = get(&l, &["fullname"]).map(|x| x.to_string());
+        let line = get(&l, &["line"]).map(|x| x.to_u32());
+        if let Some(MIRepr::Array(v)) = get(&l, &["line_asm_insn"]) {
+            instructions.extend(v.iter().filter_map(instruction_from_repr).map(|i| {
+                mi_types::Instruction {
+                    file: file.clone(),
+                    line,
+                    ..i
+                }
+            }));
         }
+    }
+
+    Some(instructions)
+}
+
+fn instruction_from_repr(repr: &MIRepr) -> Option<mi_types::Instruction> {
+    Some(mi_types::Instruction {
+        address: parse_address(&get(repr, &["address"])?.to_string())?,
+        func: get(repr, &["func-name"]).map(|x| x.to_string()),
+        offset: get(repr, &["offset"]).map(|x| x.to_u32()),
+        inst: get(repr, &["inst"])?.to_string(),
+        file: None,
+        line: None,
     })
 }
 
@@ -115,19 +247,419 @@ pub fn frame(input: &mi::Output) -> Option<mi_types::Frame> {
     frame_from_repr(&repr)
 }
 
-pub fn has_exited(input: &mi::Output) -> bool {
+/// The thread GDB has selected, as told by `-thread-info`, `-thread-select`, `=thread-selected`
+/// or `*stopped`
+pub fn selected_thread(input: &mi::Output) -> Option<String> {
+    let repr = mi_repr(input)?;
+
+    let key = match input {
+        mi::Output::ResultRecord(..) => ["current-thread-id", "new-thread-id"]
+            .into_iter()
+            .find(|k| get(&repr, &[k]).is_some())?,
+        mi::Output::NotifyAsync(kind, _) if kind == "thread-selected" => "id",
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, _) => "thread-id",
+        _ => return None,
+    };
+
+    get(&repr, &[key]).map(|x| x.to_string())
+}
+
+/// The thread the inferior stopped in, `-thread-info` also tells which one it is
+pub fn stopped_thread(input: &mi::Output) -> Option<String> {
+    let repr = mi_repr(input)?;
+
     match input {
-        mi::Output::ExecAsync(state, repr) => match get(repr, &["reason"]) {
-            Some(r) => {
-                if let MIRepr::Literal(s) = r {
-                    s == "exited-normally"
-                } else {
-                    false
-                }
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, _) => {
+            get(&repr, &["thread-id"]).map(|x| x.to_string())
+        }
+        mi::Output::ResultRecord(..) if get(&repr, &["threads"]).is_some() => {
+            get(&repr, &["current-thread-id"]).map(|x| x.to_string())
+        }
+        _ => None,
+    }
+}
+
+pub fn stop_reason(input: &mi::Output) -> Option<String> {
+    match input {
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, repr) => {
+            get(repr, &["reason"]).map(|x| x.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The `thread-id` of `*running`, a thread id or `all`
+pub fn running_thread(input: &mi::Output) -> Option<String> {
+    match input {
+        mi::Output::ExecAsync(mi_types::ExecutionState::Running, repr) => {
+            get(repr, &["thread-id"]).map(|x| x.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// `stopped-threads` of `*stopped`, thread ids or just `all`
+pub fn stopped_threads(input: &mi::Output) -> Option<Vec<String>> {
+    match input {
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, repr) => {
+            match get(repr, &["stopped-threads"])? {
+                MIRepr::Array(v) => Some(v.iter().map(|x| x.to_string()).collect()),
+                x => Some(vec![x.to_string()]),
             }
-            _ => false,
-        },
-        _ => false,
+        }
+        _ => None,
+    }
+}
+
+/// The notifications about thread groups, and about the threads in them
+pub fn thread_group_event(input: &mi::Output) -> Option<mi_types::AsyncInfo> {
+    use mi_types::AsyncInfo;
+
+    let (kind, repr) = match input {
+        mi::Output::NotifyAsync(kind, repr) => (kind, repr),
+        _ => return None,
+    };
+    let field = |name: &str| get(repr, &[name]).map(|x| x.to_string());
+    let id = field("id")?;
+
+    match kind.as_str() {
+        "thread-group-added" => Some(AsyncInfo::ThreadGroupAdded { id }),
+        "thread-group-removed" => Some(AsyncInfo::ThreadGroupRemoved { id }),
+        "thread-group-started" => Some(AsyncInfo::ThreadGroupStarted {
+            id,
+            pid: field("pid")?,
+        }),
+        "thread-group-exited" => Some(AsyncInfo::ThreadGroupExited {
+            id,
+            exit_code: field("exit-code"),
+        }),
+        "thread-created" => Some(AsyncInfo::ThreadCreated {
+            id,
+            group_id: field("group-id")?,
+        }),
+        "thread-exited" => Some(AsyncInfo::ThreadExited {
+            id,
+            group_id: field("group-id")?,
+        }),
+        _ => None,
+    }
+}
+
+/// Querying the output of -list-thread-groups
+pub fn thread_groups(input: &mi::Output) -> Option<Vec<crate::inferiors::Inferior>> {
+    let groups = match get(&mi_repr(input)?, &["groups"])? {
+        MIRepr::Array(v) => v,
+        _ => return None,
+    };
+
+    Some(
+        groups
+            .iter()
+            .filter_map(|g| {
+                Some(crate::inferiors::Inferior {
+                    id: get(g, &["id"])?.to_string(),
+                    pid: get(g, &["pid"]).map(|x| x.to_string()),
+                    executable: get(g, &["executable"]).map(|x| x.to_string()),
+                    ..Default::default()
+                })
+            })
+            .collect(),
+    )
+}
+
+/// `=thread-created` and `=thread-exited`, the thread list is out of date
+pub fn threads_changed(input: &mi::Output) -> bool {
+    matches!(
+        input,
+        mi::Output::NotifyAsync(kind, _) if kind == "thread-created" || kind == "thread-exited"
+    )
+}
+
+/// The level of the selected frame, `*stopped` always selects the innermost one
+pub fn selected_frame(input: &mi::Output) -> Option<u32> {
+    match input {
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, _) => Some(0),
+        mi::Output::ExecAsync(..) => None,
+        _ => get(&mi_repr(input)?, &["frame", "level"]).map(|x| x.to_u32()),
+    }
+}
+
+/// Querying the output of -thread-info
+pub fn threads(input: &mi::Output) -> Option<Vec<mi_types::Thread>> {
+    let threads = match get(&mi_repr(input)?, &["threads"])? {
+        MIRepr::Array(v) => v,
+        _ => return None,
+    };
+
+    Some(
+        threads
+            .iter()
+            .filter_map(|t| {
+                Some(mi_types::Thread {
+                    id: get(t, &["id"])?.to_string(),
+                    target_id: get(t, &["target-id"])
+                        .map(|x| x.to_string())
+                        .unwrap_or_default(),
+                    name: get(t, &["name"]).map(|x| x.to_string()),
+                    state: get(t, &["state"])
+                        .map(|x| x.to_string())
+                        .unwrap_or_default(),
+                    frame: frame_from_repr(t),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Querying the output of -stack-list-variables
+pub fn variables(input: &mi::Output) -> Option<Vec<mi_types::Variable>> {
+    let vars = match get(&mi_repr(input)?, &["variables"])? {
+        MIRepr::Array(v) => v,
+        _ => return None,
+    };
+
+    Some(
+        vars.iter()
+            .filter_map(|v| {
+                Some(mi_types::Variable {
+                    name: get(v, &["name"])?.to_string(),
+                    var_type: get(v, &["type"]).map(|x| x.to_string()),
+                    value: get(v, &["value"]).map(|x| x.to_string()),
+                    arg: get(v, &["arg"]).is_some(),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn varobj_from_repr(repr: &MIRepr) -> Option<mi_types::VarObj> {
+    Some(mi_types::VarObj {
+        name: get(repr, &["name"])?.to_string(),
+        expression: get(repr, &["exp"]).map(|x| x.to_string()),
+        var_type: get(repr, &["type"]).map(|x| x.to_string()),
+        value: get(repr, &["value"]).map(|x| x.to_string()),
+        numchild: get(repr, &["numchild"])?.to_u32(),
+    })
+}
+
+/// Querying the output of -var-create
+pub fn varobj(input: &mi::Output) -> Option<mi_types::VarObj> {
+    match input {
+        mi::Output::ResultRecord(_, Some(repr)) => varobj_from_repr(repr),
+        _ => None,
+    }
+}
+
+/// Querying the output of -var-list-children
+pub fn varobj_children(input: &mi::Output) -> Option<Vec<mi_types::VarObj>> {
+    let repr = mi_repr(input)?;
+    // `-var-create` has a `numchild` too, but it names the object
+    if get(&repr, &["numchild"]).is_none() || get(&repr, &["name"]).is_some() {
+        return None;
+    }
+
+    // No children at all means no `children` field
+    let children = match get(&repr, &["children"]) {
+        Some(children) => frame_list(&children)
+            .into_iter()
+            .filter_map(|c| varobj_from_repr(&get(c, &["child"])?))
+            .collect(),
+        None => vec![],
+    };
+
+    Some(children)
+}
+
+/// Querying the output of -var-update
+pub fn varobj_changes(input: &mi::Output) -> Option<Vec<mi_types::VarChange>> {
+    let changes = match get(&mi_repr(input)?, &["changelist"])? {
+        MIRepr::Array(v) => v,
+        _ => return None,
+    };
+
+    Some(
+        changes
+            .iter()
+            .filter_map(|c| {
+                Some(mi_types::VarChange {
+                    name: get(c, &["name"])?.to_string(),
+                    value: get(c, &["value"]).map(|x| x.to_string()),
+                    in_scope: get(c, &["in_scope"])
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| "true".to_string()),
+                    new_type: get(c, &["new_type"]).map(|x| x.to_string()),
+                    new_numchild: get(c, &["new_num_children"]).map(|x| x.to_u32()),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// The answer to -data-evaluate-expression or -break-watch: the value, the watchpoint number,
+/// or the error message
+pub fn evaluation(input: &mi::Output) -> Option<Result<String, String>> {
+    match input {
+        mi::Output::ResultRecord(mi_types::MIResult::Error { msg, .. }, _) => {
+            Some(Err(msg.clone()))
+        }
+        mi::Output::ResultRecord(_, Some(repr)) => get(repr, &["value"])
+            .or_else(|| get(repr, &["wpt", "number"]))
+            .map(|x| Ok(x.to_string())),
+        _ => None,
+    }
+}
+
+/// Querying the output of -complete, the line completed as far as it goes and every match
+pub fn completion(input: &mi::Output) -> Option<(Option<String>, Vec<String>)> {
+    let repr = mi_repr(input)?;
+    let matches = match get(&repr, &["matches"])? {
+        MIRepr::Array(v) => v.iter().map(|m| m.to_string()).collect(),
+        _ => vec![],
+    };
+
+    Some((get(&repr, &["completion"]).map(|c| c.to_string()), matches))
+}
+
+/// Querying the output of -data-read-memory-bytes, the address of the first byte and the bytes.
+/// Only the first block is kept, GDB splits the answer where memory can't be read.
+pub fn memory(input: &mi::Output) -> Option<(u64, Vec<u8>)> {
+    let block = match get(&mi_repr(input)?, &["memory"])? {
+        MIRepr::Array(v) => v.first()?.clone(),
+        m @ MIRepr::Map(_) => m,
+        _ => return None,
+    };
+
+    let begin = parse_address(&get(&block, &["begin"])?.to_string())?;
+    let offset = parse_address(&get(&block, &["offset"])?.to_string()).unwrap_or(0);
+    let contents = crate::memory::parse_contents(&get(&block, &["contents"])?.to_string())?;
+
+    Some((begin + offset, contents))
+}
+
+/// The number and address of the breakpoint in `-break-insert`'s answer or in
+/// `=breakpoint-created` and `=breakpoint-modified`
+pub fn breakpoint(input: &mi::Output) -> Option<(String, u64)> {
+    let repr = mi_repr(input)?;
+
+    Some((
+        get(&repr, &["bkpt", "number"])?.to_string(),
+        parse_address(&get(&repr, &["bkpt", "addr"])?.to_string())?,
+    ))
+}
+
+pub fn breakpoint_deleted(input: &mi::Output) -> Option<String> {
+    match input {
+        mi::Output::NotifyAsync(kind, repr) if kind == "breakpoint-deleted" => {
+            Some(get(repr, &["id"])?.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// `*stopped,reason="watchpoint-scope"`, the number of the watchpoint GDB deleted
+pub fn watchpoint_scope(input: &mi::Output) -> Option<String> {
+    match input {
+        mi::Output::ExecAsync(_, repr)
+            if get(repr, &["reason"])?.to_string() == "watchpoint-scope" =>
+        {
+            Some(get(repr, &["wpnum"])?.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The frame address (the CFA) from the first line of `info frame`, `Stack level 0, frame at
+/// 0x7fffffffe0f0:`. GDB tells frames apart by it and their function, MI has no command for it.
+pub fn frame_address(input: &mi::Output) -> Option<u64> {
+    match input {
+        mi::Output::ConsoleStream(s) => {
+            let s = s.strip_prefix("Stack level ")?;
+            let (_, s) = s.split_once(", frame at ")?;
+            parse_address(s.lines().next()?.strip_suffix(':')?)
+        }
+        _ => None,
+    }
+}
+
+/// `=memory-changed`, the address and length of what was written
+pub fn memory_changed(input: &mi::Output) -> Option<(u64, u64)> {
+    match input {
+        mi::Output::NotifyAsync(kind, repr) if kind == "memory-changed" => Some((
+            parse_address(&get(repr, &["addr"])?.to_string())?,
+            parse_address(&get(repr, &["len"])?.to_string())?,
+        )),
+        _ => None,
+    }
+}
+
+/// The signal from `*stopped,reason="signal-received"` or, for core files, from the
+/// `Program terminated with signal SIGSEGV, Segmentation fault.` console message
+pub fn signal(input: &mi::Output) -> Option<mi_types::Signal> {
+    match input {
+        mi::Output::ExecAsync(_, repr) => {
+            let reason = get(repr, &["reason"])?.to_string();
+            if reason != "signal-received" && reason != "exited-signalled" {
+                return None;
+            }
+
+            Some(mi_types::Signal {
+                name: get(repr, &["signal-name"])?.to_string(),
+                meaning: get(repr, &["signal-meaning"])
+                    .map(|x| x.to_string())
+                    .unwrap_or_default(),
+            })
+        }
+        mi::Output::ConsoleStream(s) => {
+            let s = s.trim().strip_prefix("Program terminated with signal ")?;
+            let (name, meaning) = s.split_once(", ")?;
+
+            Some(mi_types::Signal {
+                name: name.to_string(),
+                meaning: meaning.trim_end_matches('.').to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn has_stopped(input: &mi::Output) -> bool {
+    matches!(
+        input,
+        mi::Output::ExecAsync(mi_types::ExecutionState::Stopped, _)
+    )
+}
+
+/// The value shown by `-exec-finish`, it comes in the `*stopped` record
+pub fn return_value(input: &mi::Output) -> Option<String> {
+    let repr = mi_repr(input)?;
+
+    get(&repr, &["return-value"]).map(|x| x.to_string())
+}
+
+pub fn exit_status(input: &mi::Output) -> Option<mi_types::ExitStatus> {
+    use mi_types::ExitStatus;
+
+    let repr = match input {
+        mi::Output::ExecAsync(_, repr) => repr,
+        _ => return None,
+    };
+
+    match get(repr, &["reason"])?.to_string().as_str() {
+        "exited-normally" => Some(ExitStatus::Normal),
+        "exited" => Some(ExitStatus::Code(
+            get(repr, &["exit-code"])
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        )),
+        "exited-signalled" => Some(ExitStatus::Signal {
+            name: get(repr, &["signal-name"])
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+            meaning: get(repr, &["signal-meaning"])
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        }),
+        _ => None,
     }
 }
 
@@ -153,7 +685,280 @@ mod tests {
             r#"stack=[frame={level="0",addr="0x000000000040115a",func="fib",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="10",arch="i386:x86-64"},frame={level="1",addr="0x0000000000401167",func="fib",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="10",arch="i386:x86-64"},frame={level="2",addr="0x0000000000401167",func="fib",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="10",arch="i386:x86-64"},frame={level="3",addr="0x0000000000401167",func="fib",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="10",arch="i386:x86-64"},frame={level="4",addr="0x000000000040119a",func="main",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="15",arch="i386:x86-64"}]"#,
         ).unwrap().1;
 
-        println!("{:?}", frames_from_repr(&v));
+        let frames = frames_from_repr(&v).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[4].level, 4);
+        assert_eq!(frames[4].addr, Some(0x40119a));
+        assert_eq!(frames[4].line, Some(15));
+        assert_eq!(
+            frames[4].fullname.as_deref(),
+            Some("/home/gbrls/Programming/iron-dbg/res/example.c")
+        );
+        assert_eq!(frames[4].arch.as_deref(), Some("i386:x86-64"));
+    }
+
+    #[test]
+    fn test_return_value() {
+        let (_, out) = mi::parse_stream(
+            r#"*stopped,reason="function-finished",frame={addr="0x000000000040119a",func="main",args=[],file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="14",arch="i386:x86-64"},gdb-result-var="$1",return-value="5",thread-id="1",stopped-threads="all",core="3""#,
+        )
+        .unwrap();
+
+        assert!(has_stopped(&out));
+        assert_eq!(return_value(&out), Some("5".to_string()));
+    }
+
+    #[test]
+    fn test_exit_status() {
+        use mi_types::ExitStatus;
+
+        let (_, out) = mi::parse_stream(r#"*stopped,reason="exited-normally""#).unwrap();
+        assert_eq!(exit_status(&out), Some(ExitStatus::Normal));
+
+        let (_, out) = mi::parse_stream(r#"*stopped,reason="exited",exit-code="01""#).unwrap();
+        assert_eq!(exit_status(&out), Some(ExitStatus::Code("01".into())));
+
+        let (_, out) = mi::parse_stream(
+            r#"*stopped,reason="exited-signalled",signal-name="SIGSEGV",signal-meaning="Segmentation fault""#,
+        )
+        .unwrap();
+        assert_eq!(
+            exit_status(&out),
+            Some(ExitStatus::Signal {
+                name: "SIGSEGV".into(),
+                meaning: "Segmentation fault".into()
+            })
+        );
+
+        let (_, out) = mi::parse_stream(r#"*stopped,reason="end-stepping-range""#).unwrap();
+        assert_eq!(exit_status(&out), None);
+        assert_eq!(watchpoint_scope(&out), None);
+
+        let (_, out) = mi::parse_stream(
+            r#"*stopped,reason="watchpoint-scope",wpnum="2",frame={func="main",args=[]},thread-id="1""#,
+        )
+        .unwrap();
+        assert_eq!(watchpoint_scope(&out), Some("2".into()));
+    }
+
+    #[test]
+    fn test_frame_address() {
+        let (_, out) = mi::parse_stream(r#"~"Stack level 1, frame at 0x7fffffffe0f0:\n""#).unwrap();
+        assert_eq!(frame_address(&out), Some(0x7fffffffe0f0));
+
+        let (_, out) =
+            mi::parse_stream(r#"~" rip = 0x401136 in fib (example.c:8); saved rip = 0x401167\n""#)
+                .unwrap();
+        assert_eq!(frame_address(&out), None);
+    }
+
+    #[test]
+    fn test_single_frame() {
+        let (_, out) = mi::parse_stream(
+            r#"^done,stack=[frame={level="0",addr="0x0000000000401136",func="main",file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="14",arch="i386:x86-64"}]"#,
+        )
+        .unwrap();
+        assert_eq!(frames(&out).unwrap().len(), 1);
+
+        let (_, out) = mi::parse_stream(r#"^done,stack=[]"#).unwrap();
+        assert_eq!(frames(&out), Some(vec![]));
+    }
+
+    #[test]
+    fn test_threads() {
+        let (_, out) = mi::parse_stream(
+            r#"^done,threads=[{id="2",target-id="Thread 0x7ffff7d89640 (LWP 4243)",name="worker",frame={level="0",addr="0x00007ffff7e2f1a5",func="__futex_abstimed_wait_common",args=[],arch="i386:x86-64"},state="stopped",core="1"},{id="1",target-id="Thread 0x7ffff7d8a740 (LWP 4242)",name="a.out",frame={level="0",addr="0x0000000000401136",func="main",args=[],file="example.c",fullname="/home/gbrls/Programming/iron-dbg/res/example.c",line="14",arch="i386:x86-64"},state="stopped",core="3"}],current-thread-id="1""#,
+        )
+        .unwrap();
+
+        let threads = threads(&out).unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].name, Some("worker".to_string()));
+        assert_eq!(threads[1].frame.as_ref().unwrap().func, "main");
+        assert_eq!(selected_thread(&out), Some("1".to_string()));
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,new-thread-id="2",frame={level="0",addr="0x00007ffff7e2f1a5",func="__futex_abstimed_wait_common",args=[]}"#,
+        )
+        .unwrap();
+        assert_eq!(selected_thread(&out), Some("2".to_string()));
+        assert_eq!(selected_frame(&out), Some(0));
+    }
+
+    #[test]
+    fn test_variables() {
+        let (_, out) = mi::parse_stream(
+            r#"^done,variables=[{name="a",arg="1",type="int",value="5"},{name="s",type="char *",value="0x402004 \"hello\""},{name="p",type="struct point"}]"#,
+        )
+        .unwrap();
+
+        let vars = variables(&out).unwrap();
+        assert_eq!(vars.len(), 3);
+        assert!(vars[0].arg);
+        assert_eq!(vars[1].value, Some("0x402004 \"hello\"".to_string()));
+        assert_eq!(vars[2].var_type, Some("struct point".to_string()));
+        assert_eq!(vars[2].value, None);
+    }
+
+    #[test]
+    fn test_varobjs() {
+        let (_, out) = mi::parse_stream(
+            r#"^done,name="v1",numchild="2",value="{...}",type="struct point",thread-id="1",has_more="0""#,
+        )
+        .unwrap();
+        let obj = varobj(&out).unwrap();
+        assert_eq!(obj.name, "v1");
+        assert_eq!(obj.numchild, 2);
+        assert_eq!(obj.var_type, Some("struct point".to_string()));
+        assert_eq!(varobj_children(&out), None);
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,numchild="2",children=[child={name="v1.x",exp="x",numchild="0",value="1",type="int",thread-id="1"},child={name="v1.y",exp="y",numchild="0",value="2",type="int",thread-id="1"}],has_more="0""#,
+        )
+        .unwrap();
+        let children = varobj_children(&out).unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].name, "v1.y");
+        assert_eq!(children[1].expression, Some("y".to_string()));
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,changelist=[{name="v1.x",value="3",in_scope="true",type_changed="false",has_more="0"},{name="v2",in_scope="false",type_changed="false",has_more="0"}]"#,
+        )
+        .unwrap();
+        let changes = varobj_changes(&out).unwrap();
+        assert_eq!(changes[0].value, Some("3".to_string()));
+        assert_eq!(changes[1].in_scope, "false");
+    }
+
+    #[test]
+    fn test_evaluation() {
+        let (_, out) = mi::parse_stream(r#"1000^done,value="{x = 1, y = 2}""#).unwrap();
+        assert_eq!(evaluation(&out), Some(Ok("{x = 1, y = 2}".to_string())));
+
+        let (_, out) = mi::parse_stream(r#"2000^done,wpt={number="3",exp="a"}"#).unwrap();
+        assert_eq!(evaluation(&out), Some(Ok("3".to_string())));
+
+        let (_, out) =
+            mi::parse_stream(r#"1001^error,msg="No symbol \"b\" in current context.""#).unwrap();
+        assert_eq!(
+            evaluation(&out),
+            Some(Err("No symbol \"b\" in current context.".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_mixed_instructions() {
+        let (_, out) = mi::parse_stream(
+            r#"4^done,asm_insns=[src_and_asm_line={line="5",file="fib.c",fullname="/src/fib.c",line_asm_insn=[{address="0x0000000000401126",func-name="fib",offset="0",inst="push   rbp"},{address="0x0000000000401127",func-name="fib",offset="1",inst="mov    rbp,rsp"}]},src_and_asm_line={line="6",file="fib.c",fullname="/src/fib.c",line_asm_insn=[{address="0x000000000040112a",func-name="fib",offset="4",inst="ret"}]}]"#,
+        )
+        .unwrap();
+        let insns = instructions(&out).unwrap();
+        assert_eq!(insns.len(), 3);
+        assert_eq!(insns[1].address, 0x401127);
+        assert_eq!(insns[1].file.as_deref(), Some("/src/fib.c"));
+        assert_eq!(insns[2].line, Some(6));
+
+        // No line information, mixed mode answers like mode 0
+        let (_, out) = mi::parse_stream(
+            r#"4^done,asm_insns=[{address="0x0000000000401000",inst="endbr64"},{address="0x0000000000401004",inst="sub    rsp,0x8"}]"#,
+        )
+        .unwrap();
+        let insns = instructions(&out).unwrap();
+        assert_eq!(insns.len(), 2);
+        assert_eq!(insns[0].line, None);
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,bkpt={number="2",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000401130",func="fib",thread-groups=["i1"],times="0"}"#,
+        )
+        .unwrap();
+        assert_eq!(breakpoint(&out), Some(("2".to_string(), 0x401130)));
+
+        let (_, out) = mi::parse_stream(r#"=breakpoint-deleted,id="2""#).unwrap();
+        assert_eq!(breakpoint_deleted(&out), Some("2".to_string()));
+    }
+
+    #[test]
+    fn test_thread_groups() {
+        let (_, out) = mi::parse_stream(r#"=thread-group-started,id="i2",pid="4242""#).unwrap();
+        assert_eq!(
+            thread_group_event(&out),
+            Some(mi_types::AsyncInfo::ThreadGroupStarted {
+                id: "i2".into(),
+                pid: "4242".into()
+            })
+        );
+
+        let (_, out) = mi::parse_stream(r#"=thread-group-exited,id="i1""#).unwrap();
+        assert_eq!(
+            thread_group_event(&out),
+            Some(mi_types::AsyncInfo::ThreadGroupExited {
+                id: "i1".into(),
+                exit_code: None
+            })
+        );
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,groups=[{id="i1",type="process",pid="4241",executable="/usr/sbin/daemon",cores=["1"]},{id="i2",type="process"}]"#,
+        )
+        .unwrap();
+        let groups = thread_groups(&out).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].executable.as_deref(), Some("/usr/sbin/daemon"));
+        assert_eq!(groups[1].pid, None);
+    }
+
+    #[test]
+    fn test_memory() {
+        let (_, out) = mi::parse_stream(
+            r#"3^done,memory=[{begin="0x00007fffffffe000",offset="0x0000000000000000",end="0x00007fffffffe004",contents="01ff0a00"}]"#,
+        )
+        .unwrap();
+        assert_eq!(memory(&out), Some((0x7fffffffe000, vec![1, 0xff, 0x0a, 0])));
+
+        let (_, out) =
+            mi::parse_stream(r#"=memory-changed,thread-group="i1",addr="0x00404028",len="0x4""#)
+                .unwrap();
+        assert_eq!(memory_changed(&out), Some((0x404028, 4)));
+    }
+
+    #[test]
+    fn test_completion() {
+        let (_, out) = mi::parse_stream(
+            r#"7^done,completion="break",matches=["break","break-range"],max_completions_reached="0""#,
+        )
+        .unwrap();
+        assert_eq!(
+            completion(&out),
+            Some((
+                Some("break".to_string()),
+                vec!["break".to_string(), "break-range".to_string()]
+            ))
+        );
+
+        let (_, out) =
+            mi::parse_stream(r#"7^done,matches=[],max_completions_reached="0""#).unwrap();
+        assert_eq!(completion(&out), Some((None, vec![])));
+    }
+
+    #[test]
+    fn test_signal() {
+        let (_, out) = mi::parse_stream(
+            r#"*stopped,reason="signal-received",signal-name="SIGSEGV",signal-meaning="Segmentation fault",frame={addr="0x0000000000401136",func="main",args=[]},thread-id="1",stopped-threads="all",core="2""#,
+        )
+        .unwrap();
+        assert_eq!(
+            signal(&out),
+            Some(mi_types::Signal {
+                name: "SIGSEGV".into(),
+                meaning: "Segmentation fault".into()
+            })
+        );
+
+        let out = mi::Output::ConsoleStream(
+            "Program terminated with signal SIGABRT, Aborted.\n".to_string(),
+        );
+        assert_eq!(signal(&out).unwrap().name, "SIGABRT");
+        assert_eq!(signal(&out).unwrap().meaning, "Aborted");
     }
 
     #[test]
@@ -163,5 +968,54 @@ mod tests {
             .1;
 
         println!("{v:#?}");
+
+        let args = frame_args_from_repr(&v).unwrap();
+        assert_eq!(args.len(), 6);
+        assert_eq!(
+            args[3],
+            (
+                3,
+                vec![("a".to_string(), "int".to_string(), "4".to_string())]
+            )
+        );
+        assert_eq!(args[5], (5, vec![]));
+    }
+
+    #[test]
+    fn test_report_queries() {
+        let (_, out) = mi::parse_stream(
+            r#"^done,thread-ids={thread-id="3",thread-id="1"},current-thread-id="1",number-of-threads="2""#,
+        )
+        .unwrap();
+        assert_eq!(
+            thread_ids(&out),
+            Some(vec!["3".to_string(), "1".to_string()])
+        );
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,thread-ids={thread-id="1"},current-thread-id="1",number-of-threads="1""#,
+        )
+        .unwrap();
+        assert_eq!(thread_ids(&out), Some(vec!["1".to_string()]));
+
+        let (_, out) = mi::parse_stream(r#"^done,register-names=["rax","rbx","","rip"]"#).unwrap();
+        assert_eq!(register_names(&out).unwrap()[3], "rip");
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,register-values=[{number="0",value="0x1c"},{number="16",value="0x401136"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            register_values(&out),
+            Some(vec![(0, "0x1c".to_string()), (16, "0x401136".to_string())])
+        );
+
+        let (_, out) = mi::parse_stream(
+            r#"^done,asm_insns=[{address="0x0000000000401126",func-name="main",offset="0",inst="push   rbp"},{address="0x0000000000401127",func-name="main",offset="1",inst="mov    rbp,rsp"}]"#,
+        )
+        .unwrap();
+        let insts = instructions(&out).unwrap();
+        assert_eq!(insts[1].address, 0x401127);
+        assert_eq!(insts[1].inst, "mov    rbp,rsp");
     }
 }
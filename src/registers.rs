@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+/// How a register value is shown, GDB always gives it to us in hex
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Format {
+    #[default]
+    Hex,
+    Decimal,
+    Binary,
+    /// The bits of the register read as an `f64`
+    Float,
+}
+
+impl Format {
+    pub fn next(self) -> Format {
+        match self {
+            Format::Hex => Format::Decimal,
+            Format::Decimal => Format::Binary,
+            Format::Binary => Format::Float,
+            Format::Float => Format::Hex,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Hex => "hex",
+            Format::Decimal => "dec",
+            Format::Binary => "bin",
+            Format::Float => "float",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Group {
+    General,
+    Flags,
+    Segment,
+    Vector,
+    /// x87 control registers and whatever else the architecture has
+    Other,
+}
+
+pub const GROUPS: &[Group] = &[
+    Group::General,
+    Group::Flags,
+    Group::Segment,
+    Group::Vector,
+    Group::Other,
+];
+
+impl Group {
+    /// Guessed from the name, GDB's register groups aren't available through MI
+    pub fn of(name: &str) -> Group {
+        const GENERAL: &[&str] = &[
+            "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "rip", "eax", "ebx", "ecx",
+            "edx", "esi", "edi", "ebp", "esp", "eip", "pc", "sp", "lr", "fp",
+        ];
+        const SEGMENT: &[&str] = &["cs", "ss", "ds", "es", "fs", "gs", "fs_base", "gs_base"];
+        const FLAGS: &[&str] = &["eflags", "rflags", "cpsr", "fpsr", "fpcr", "mxcsr"];
+        const VECTOR: &[&str] = &["xmm", "ymm", "zmm", "st", "mm", "k"];
+
+        let numbered = |prefix: &str| {
+            name.strip_prefix(prefix)
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        };
+
+        if GENERAL.contains(&name) || numbered("r") || numbered("x") || numbered("w") {
+            Group::General
+        } else if FLAGS.contains(&name) {
+            Group::Flags
+        } else if SEGMENT.contains(&name) {
+            Group::Segment
+        } else if VECTOR.iter().any(|p| numbered(p)) || numbered("v") || numbered("q") {
+            Group::Vector
+        } else {
+            Group::Other
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Group::General => "General purpose",
+            Group::Flags => "Flags",
+            Group::Segment => "Segment",
+            Group::Vector => "Vector",
+            Group::Other => "Other",
+        }
+    }
+}
+
+/// What the registers panel remembers between frames
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RegisterView {
+    /// Per register number, hex when it's not there
+    pub formats: HashMap<u32, Format>,
+    /// The register being edited and the text typed so far
+    pub editing: Option<(u32, String)>,
+}
+
+/// `value` as GDB printed it in hex. Vector registers are printed as structs, those are kept as
+/// they are.
+pub fn format(value: &str, format: Format) -> String {
+    let n = match value
+        .strip_prefix("0x")
+        .and_then(|v| u128::from_str_radix(v, 16).ok())
+    {
+        Some(n) => n,
+        None => return value.to_string(),
+    };
+
+    match format {
+        Format::Hex => value.to_string(),
+        Format::Decimal => n.to_string(),
+        Format::Binary => format!("{n:#b}"),
+        Format::Float => match u64::try_from(n) {
+            Ok(bits) => f64::from_bits(bits).to_string(),
+            Err(_) => value.to_string(),
+        },
+    }
+}
+
+/// Turns what the user typed, in `format`, into something GDB can assign to a register
+pub fn parse_input(input: &str, format: Format) -> Option<String> {
+    let input = input.trim();
+
+    match format {
+        Format::Hex => {
+            let digits = input.strip_prefix("0x").unwrap_or(input);
+            u128::from_str_radix(digits, 16)
+                .ok()
+                .map(|n| format!("{n:#x}"))
+        }
+        Format::Decimal => input.parse::<i128>().ok().map(|n| n.to_string()),
+        Format::Binary => {
+            let digits = input.strip_prefix("0b").unwrap_or(input);
+            u128::from_str_radix(digits, 2).ok().map(|n| n.to_string())
+        }
+        Format::Float => input
+            .parse::<f64>()
+            .ok()
+            .map(|f| format!("{:#x}", f.to_bits())),
+    }
+}
+
+/// Writes `value` to the register and reads the registers again
+pub fn write_commands(name: &str, value: &str) -> Vec<String> {
+    vec![
+        format!("-data-evaluate-expression \"${name} = {value}\""),
+        "-data-list-register-values x".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format("0x10", Format::Hex), "0x10");
+        assert_eq!(format("0x10", Format::Decimal), "16");
+        assert_eq!(format("0x5", Format::Binary), "0b101");
+        assert_eq!(format("0x3ff0000000000000", Format::Float), "1");
+        assert_eq!(
+            format("{v4_float = {0x0, 0x0, 0x0, 0x0}}", Format::Decimal),
+            "{v4_float = {0x0, 0x0, 0x0, 0x0}}"
+        );
+
+        assert_eq!(parse_input("ff", Format::Hex), Some("0xff".to_string()));
+        assert_eq!(parse_input("-1", Format::Decimal), Some("-1".to_string()));
+        assert_eq!(parse_input("0b101", Format::Binary), Some("5".to_string()));
+        assert_eq!(
+            parse_input("1", Format::Float),
+            Some("0x3ff0000000000000".to_string())
+        );
+        assert_eq!(parse_input("zz", Format::Hex), None);
+    }
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(Group::of("rax"), Group::General);
+        assert_eq!(Group::of("r12"), Group::General);
+        assert_eq!(Group::of("eflags"), Group::Flags);
+        assert_eq!(Group::of("fs_base"), Group::Segment);
+        assert_eq!(Group::of("xmm15"), Group::Vector);
+        assert_eq!(Group::of("st0"), Group::Vector);
+        assert_eq!(Group::of("fctrl"), Group::Other);
+    }
+}
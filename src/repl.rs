@@ -0,0 +1,477 @@
+use crate::mi::escape;
+use crate::tokens::{self, REPL_TYPES, REPL_VALUES};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Entries are never given an id that was used before, see `Entry::id`
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// What `--format` takes, the first one is GDB's default
+pub const FORMATS: &[&str] = &[
+    "natural",
+    "hexadecimal",
+    "decimal",
+    "octal",
+    "binary",
+    "zero-hexadecimal",
+];
+
+/// A value as GDB prints it, split so it can be shown by type
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    /// `{x = 1, y = 2}`, base classes are fields named `<Base>`
+    Struct(Vec<(String, Value)>),
+    /// `{1, 2, 3}`
+    Array(Vec<Value>),
+    /// A char array, `"abc", '\000' <repeats 12 times>`
+    Text(String),
+    /// `0x4005d0 <buf>`, `(int *) 0x7fffffffe0cc` or a `char *` with its string
+    Pointer {
+        address: u64,
+        text: String,
+    },
+    Scalar(String),
+}
+
+/// An expression typed in the REPL and what GDB said about it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    /// Picks its tokens and the name of its variable object, so an answer for a cleared entry
+    /// finds nothing
+    pub id: u64,
+    pub expression: String,
+    pub format: String,
+    pub var_type: Option<String>,
+    /// `None` until GDB answers
+    pub result: Option<Result<Value, String>>,
+    /// What GDB printed, read again once the type is known
+    text: Option<String>,
+}
+
+impl Entry {
+    fn read(&mut self) {
+        if let Some(text) = &self.text {
+            self.result = Some(Ok(parse(text, self.var_type.as_deref(), &self.format)));
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Repl {
+    pub entries: Vec<Entry>,
+    /// Console text since the last result record, `whatis` prints the type there
+    console: String,
+}
+
+/// What's being typed in the REPL
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ReplView {
+    pub input: String,
+    /// Index in `FORMATS`
+    pub format: usize,
+}
+
+/// What can be done from the REPL
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReplEdit {
+    Evaluate {
+        expression: String,
+        format: String,
+    },
+    Clear,
+    /// A pointer was clicked, the memory view goes there
+    ShowMemory(u64),
+}
+
+impl Repl {
+    /// The answer to `-data-evaluate-expression`, or the error
+    pub fn value_answered(&mut self, token: u64, answer: Result<String, String>) {
+        if let Some(e) = entry(&mut self.entries, REPL_VALUES, token) {
+            match answer {
+                Ok(text) => {
+                    e.text = Some(text);
+                    e.read();
+                }
+                Err(msg) => e.result = Some(Err(msg)),
+            }
+        }
+    }
+
+    /// GDB runs one command at a time, so the console text belongs to the next result record
+    pub fn console_output(&mut self, s: &str) {
+        self.console.push_str(s);
+    }
+
+    /// Any result record ends the console text, the one of `whatis` has the type in it
+    pub fn type_answered(&mut self, token: Option<u64>) {
+        let console = std::mem::take(&mut self.console);
+
+        if let Some(e) = token.and_then(|t| entry(&mut self.entries, REPL_TYPES, t)) {
+            e.var_type = console.trim().strip_prefix("type = ").map(String::from);
+            e.read();
+        }
+    }
+}
+
+/// The entry whose id is item `token` of `range`
+fn entry(entries: &mut [Entry], range: Range<u64>, token: u64) -> Option<&mut Entry> {
+    entries
+        .iter_mut()
+        .find(|e| tokens::nth(range.clone(), e.id) == token)
+}
+
+pub fn is_value_token(token: Option<u64>) -> bool {
+    tokens::index(REPL_VALUES, token).is_some()
+}
+
+/// Evaluates in the selected frame, which is GDB's own selection. The natural format is GDB's
+/// default so it's left out. The type comes from `whatis`, which doesn't evaluate, so side effects
+/// happen once.
+pub fn apply(repl: &mut Repl, edit: ReplEdit) -> Vec<String> {
+    match edit {
+        ReplEdit::Evaluate { expression, format } => {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let exp = escape(&expression);
+            let format_arg = if format == FORMATS[0] {
+                String::new()
+            } else {
+                format!("--format {format} ")
+            };
+
+            repl.entries.push(Entry {
+                id,
+                expression,
+                format,
+                var_type: None,
+                result: None,
+                text: None,
+            });
+
+            vec![
+                format!(
+                    "{}-data-evaluate-expression {format_arg}\"{exp}\"",
+                    tokens::nth(REPL_VALUES, id)
+                ),
+                format!(
+                    "{}-interpreter-exec console \"whatis {exp}\"",
+                    tokens::nth(REPL_TYPES, id)
+                ),
+            ]
+        }
+        // Answers still on their way find no entry
+        ReplEdit::Clear => {
+            repl.entries.clear();
+            vec![]
+        }
+        ReplEdit::ShowMemory(_) => vec![],
+    }
+}
+
+/// Reads what `print` shows in `format`. The type says what the value is when we have it. The
+/// members of a struct have none, their text decides, and only in the natural format since the
+/// others print every integer like an address. Anything it doesn't understand stays a scalar with
+/// the text as is.
+pub fn parse(s: &str, var_type: Option<&str>, format: &str) -> Value {
+    let s = s.trim();
+
+    let var_type = match var_type.map(str::trim) {
+        Some(t) => t,
+        None if s.starts_with('{') => return compound(s, None, format),
+        None if s.starts_with('"') => return Value::Text(s.to_string()),
+        None if format == FORMATS[0] => return pointer_or_scalar(s),
+        None => return Value::Scalar(s.to_string()),
+    };
+
+    // `int *`, `char **` or `void (*)(int)`
+    if var_type.contains('*') {
+        return pointer_or_scalar(s);
+    }
+
+    // `char [16]` has `char` elements, `int [2][3]` has `int [2]` ones
+    if let Some(element) = var_type
+        .strip_suffix(']')
+        .and_then(|t| t.rsplit_once('['))
+        .map(|(element, _)| element.trim_end())
+    {
+        return if is_char(element) && !s.starts_with('{') {
+            Value::Text(s.to_string())
+        } else {
+            compound(s, Some(element), format)
+        };
+    }
+
+    if s.starts_with('{') {
+        compound(s, None, format)
+    } else {
+        Value::Scalar(s.to_string())
+    }
+}
+
+/// A struct or an array, with the type of the elements of an array when it's known
+fn compound(s: &str, element: Option<&str>, format: &str) -> Value {
+    let inner = match s.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+        Some(inner) => inner,
+        None => return Value::Scalar(s.to_string()),
+    };
+
+    let items = split_top(inner);
+    let fields = items.iter().map(|i| field(i)).collect::<Option<Vec<_>>>();
+
+    match fields {
+        Some(fields) if !fields.is_empty() => Value::Struct(
+            fields
+                .into_iter()
+                .map(|(name, v)| (name.to_string(), parse(v, None, format)))
+                .collect(),
+        ),
+        _ => Value::Array(items.iter().map(|i| parse(i, element, format)).collect()),
+    }
+}
+
+fn pointer_or_scalar(s: &str) -> Value {
+    match pointer(s) {
+        Some(address) => Value::Pointer {
+            address,
+            text: s.to_string(),
+        },
+        None => Value::Scalar(s.to_string()),
+    }
+}
+
+/// The element types GDB prints an array of as a string
+fn is_char(element: &str) -> bool {
+    let element = element
+        .trim_start_matches("const ")
+        .trim_start_matches("volatile ");
+
+    matches!(
+        element,
+        "char" | "signed char" | "unsigned char" | "wchar_t" | "char16_t" | "char32_t"
+    )
+}
+
+/// `name = value`, the name can be `<Base>` or `[3]` but has no braces or quotes
+fn field(item: &str) -> Option<(&str, &str)> {
+    let (name, value) = item.split_once(" = ")?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_<>:[] ".contains(c));
+
+    valid.then_some((name.trim(), value))
+}
+
+/// The address of `0x4005d0 <buf>` or `(char *) 0x4006f4 "hi"`, after the cast if there's one
+fn pointer(s: &str) -> Option<u64> {
+    let s = match s.strip_prefix('(') {
+        Some(rest) => rest.split_once(") ")?.1,
+        None => s,
+    };
+
+    let digits = s.strip_prefix("0x")?;
+    let end = digits
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(digits.len());
+
+    u64::from_str_radix(&digits[..end], 16).ok()
+}
+
+/// Splits on the commas that aren't inside braces, brackets, angle brackets or quotes
+fn split_top(s: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' | '(' | '[' | '<' => depth += 1,
+            '}' | ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = s[start..].trim();
+    if !last.is_empty() {
+        items.push(last);
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::nth;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(
+                "{x = 1, p = {a = 0x0, b = 98 'b'}, name = \"a, b\"}",
+                None,
+                "natural"
+            ),
+            Value::Struct(vec![
+                ("x".into(), Value::Scalar("1".into())),
+                (
+                    "p".into(),
+                    Value::Struct(vec![
+                        (
+                            "a".into(),
+                            Value::Pointer {
+                                address: 0,
+                                text: "0x0".into()
+                            }
+                        ),
+                        ("b".into(), Value::Scalar("98 'b'".into())),
+                    ])
+                ),
+                ("name".into(), Value::Text("\"a, b\"".into())),
+            ])
+        );
+        assert_eq!(
+            parse("{1, 2, 0 <repeats 14 times>}", None, "natural"),
+            Value::Array(vec![
+                Value::Scalar("1".into()),
+                Value::Scalar("2".into()),
+                Value::Scalar("0 <repeats 14 times>".into()),
+            ])
+        );
+        assert_eq!(
+            parse("(int *) 0x7fffffffe0cc", None, "natural"),
+            Value::Pointer {
+                address: 0x7fffffffe0cc,
+                text: "(int *) 0x7fffffffe0cc".into()
+            }
+        );
+        assert_eq!(
+            parse("\"hi\", '\\000' <repeats 14 times>", None, "natural"),
+            Value::Text("\"hi\", '\\000' <repeats 14 times>".into())
+        );
+        assert_eq!(parse("-3.5", None, "natural"), Value::Scalar("-3.5".into()));
+    }
+
+    #[test]
+    fn test_parse_typed() {
+        let hex = "hexadecimal";
+
+        assert_eq!(
+            parse("0x1f", Some("int"), hex),
+            Value::Scalar("0x1f".into())
+        );
+        assert_eq!(
+            parse("{x = 0x1, next = 0x4052a0}", Some("struct node"), hex),
+            Value::Struct(vec![
+                ("x".into(), Value::Scalar("0x1".into())),
+                ("next".into(), Value::Scalar("0x4052a0".into())),
+            ])
+        );
+        assert_eq!(
+            parse("0x4052a0", Some("struct node *"), hex),
+            Value::Pointer {
+                address: 0x4052a0,
+                text: "0x4052a0".into()
+            }
+        );
+        assert_eq!(
+            parse("{0x68, 0x69}", Some("char [2]"), hex),
+            Value::Array(vec![
+                Value::Scalar("0x68".into()),
+                Value::Scalar("0x69".into()),
+            ])
+        );
+        assert_eq!(
+            parse("'\\000' <repeats 16 times>", Some("char [16]"), "natural"),
+            Value::Text("'\\000' <repeats 16 times>".into())
+        );
+        assert_eq!(
+            parse("{{1, 2}, {3, 4}}", Some("int [2][2]"), "natural"),
+            Value::Array(vec![
+                Value::Array(vec![Value::Scalar("1".into()), Value::Scalar("2".into())]),
+                Value::Array(vec![Value::Scalar("3".into()), Value::Scalar("4".into())]),
+            ])
+        );
+    }
+
+    fn evaluate(repl: &mut Repl, expression: &str, format: &str) -> Vec<String> {
+        apply(
+            repl,
+            ReplEdit::Evaluate {
+                expression: expression.into(),
+                format: format.into(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut repl = Repl::default();
+
+        let cmds = evaluate(&mut repl, "buf", "natural");
+        let buf = repl.entries[0].id;
+        let (value, var_type) = (nth(REPL_VALUES, buf), nth(REPL_TYPES, buf));
+        assert_eq!(
+            cmds,
+            vec![
+                format!(r#"{value}-data-evaluate-expression "buf""#),
+                format!(r#"{var_type}-interpreter-exec console "whatis buf""#)
+            ]
+        );
+
+        let cmds = evaluate(&mut repl, "n", "hexadecimal");
+        let n = nth(REPL_VALUES, repl.entries[1].id);
+        assert_eq!(
+            cmds[0],
+            format!(r#"{n}-data-evaluate-expression --format hexadecimal "n""#)
+        );
+        assert!(is_value_token(Some(n)));
+
+        repl.value_answered(n, Err("No symbol \"n\" in current context.".into()));
+        assert!(matches!(repl.entries[1].result, Some(Err(_))));
+
+        // The value comes before the type and is read again with it
+        repl.value_answered(value, Ok("'\\000' <repeats 16 times>".into()));
+        assert!(matches!(repl.entries[0].result, Some(Ok(Value::Scalar(_)))));
+
+        // Only the console text right before the answer is the type
+        repl.console_output("$1 = 3\n");
+        repl.type_answered(None);
+        repl.console_output("type = char [16]\n");
+        repl.type_answered(Some(var_type));
+        assert_eq!(repl.entries[0].var_type.as_deref(), Some("char [16]"));
+        assert!(matches!(repl.entries[0].result, Some(Ok(Value::Text(_)))));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut repl = Repl::default();
+
+        let before = evaluate(&mut repl, "x", "natural");
+        let old = nth(REPL_VALUES, repl.entries[0].id);
+        apply(&mut repl, ReplEdit::Clear);
+        let after = evaluate(&mut repl, "x", "natural");
+
+        // Neither the token nor the variable object is used again
+        assert_ne!(before, after);
+        repl.value_answered(old, Ok("1".into()));
+        assert_eq!(repl.entries[0].result, None);
+    }
+}
@@ -0,0 +1,411 @@
+use crate::control::PersistentData;
+use crate::mi_types::Frame;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How far we are in gathering a crash report, the data itself lives in `PersistentData`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CrashReportState {
+    /// The walk over threads and frames should end with the report commands
+    pub requested: bool,
+    /// Answers we still wait for before the report is complete
+    pub pending: usize,
+    /// Everything is in, the report can be written
+    pub ready: bool,
+    /// Console output of `info proc mappings`
+    pub memory_map: String,
+    pub written: Option<Result<PathBuf, String>>,
+}
+
+/// Writes `<name>.md` and `<name>.json` into `dir` and returns the path of the Markdown one
+pub fn write(data: &PersistentData, dir: &Path, name: &str) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let md = dir.join(format!("{name}.md"));
+    fs::write(&md, markdown(data))?;
+    fs::write(dir.join(format!("{name}.json")), json(data).to_string())?;
+
+    Ok(md)
+}
+
+/// Threads in the order `-thread-info` gave them, or by id if we don't have it
+fn thread_ids(data: &PersistentData) -> Vec<String> {
+    match &data.threads {
+        Some(threads) => threads.iter().map(|t| t.id.clone()).collect(),
+        None => {
+            let mut ids = data.thread_frames.keys().cloned().collect::<Vec<_>>();
+            ids.sort_by_key(|id| id.parse::<u32>().unwrap_or(u32::MAX));
+            ids
+        }
+    }
+}
+
+fn thread_title(data: &PersistentData, id: &str) -> String {
+    let thread = data.threads.iter().flatten().find(|t| t.id == id);
+
+    match thread {
+        Some(t) => format!(
+            "Thread {} ({}){}",
+            t.id,
+            t.target_id,
+            t.name
+                .as_ref()
+                .map(|n| format!(" \"{n}\""))
+                .unwrap_or_default()
+        ),
+        None => format!("Thread {id}"),
+    }
+}
+
+fn register_name(data: &PersistentData, number: u32) -> String {
+    data.register_names
+        .get(number as usize)
+        .filter(|n| !n.is_empty())
+        .cloned()
+        .unwrap_or_else(|| format!("${number}"))
+}
+
+/// The address the stopped thread was executing, the crash site
+fn faulting_pc(data: &PersistentData) -> Option<u64> {
+    let thread = data.stopped_thread.as_ref()?;
+    data.thread_frames.get(thread)?.first()?.addr
+}
+
+fn location(f: &Frame) -> String {
+    match (&f.file, f.line) {
+        (Some(file), Some(line)) => format!("{file}:{line}"),
+        (Some(file), None) => file.clone(),
+        _ => "??".to_string(),
+    }
+}
+
+pub fn markdown(data: &PersistentData) -> String {
+    let mut md = String::from("# Crash report\n\n");
+
+    match &data.signal {
+        Some(s) => md.push_str(&format!("**Signal:** `{}` ({})\n\n", s.name, s.meaning)),
+        None => md.push_str("**Signal:** none\n\n"),
+    }
+
+    if let Some(t) = &data.stopped_thread {
+        md.push_str(&format!("**Stopped thread:** {t}\n\n"));
+    }
+
+    md.push_str("## Threads\n\n");
+    for id in thread_ids(data) {
+        md.push_str(&format!("### {}\n\n", thread_title(data, &id)));
+
+        for f in data.thread_frames.get(&id).into_iter().flatten() {
+            let args = f
+                .args
+                .iter()
+                .flatten()
+                .map(|(name, _, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            md.push_str(&format!(
+                "- `#{} {:#018x} in {}({args}) at {}`\n",
+                f.level,
+                f.addr.unwrap_or(0),
+                f.func,
+                location(f)
+            ));
+
+            let vars = data.frame_variables.get(&(id.clone(), f.level));
+            for v in vars.into_iter().flatten().filter(|v| !v.arg) {
+                md.push_str(&format!(
+                    "    - `{} = {}`\n",
+                    v.name,
+                    v.value.as_deref().unwrap_or("...")
+                ));
+            }
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## Registers\n\n```\n");
+    for (number, value) in &data.registers {
+        md.push_str(&format!("{:<8} {value}\n", register_name(data, *number)));
+    }
+    md.push_str("```\n\n");
+
+    md.push_str("## Disassembly\n\n```\n");
+    let pc = faulting_pc(data);
+    for i in &data.disassembly.instructions {
+        let marker = if Some(i.address) == pc { "=>" } else { "  " };
+        let symbol = match (&i.func, i.offset) {
+            (Some(func), Some(offset)) => format!(" <{func}+{offset}>"),
+            _ => String::new(),
+        };
+        md.push_str(&format!(
+            "{marker} {:#018x}{symbol}: {}\n",
+            i.address, i.inst
+        ));
+    }
+    md.push_str("```\n\n");
+
+    md.push_str("## Memory map\n\n```\n");
+    md.push_str(data.report.memory_map.trim_end());
+    md.push_str("\n```\n");
+
+    md
+}
+
+/// Just enough JSON to write the report, we don't need to read it back
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn opt_str(s: Option<&str>) -> Json {
+        s.map(|s| Json::Str(s.to_string())).unwrap_or(Json::Null)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\t' => write!(f, "\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Array(v) => {
+                write!(f, "[")?;
+                for (i, x) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(v) => {
+                write!(f, "{{")?;
+                for (i, (k, x)) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{x}", Json::Str(k.to_string()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+pub fn json(data: &PersistentData) -> Json {
+    let signal = match &data.signal {
+        Some(s) => Json::Object(vec![
+            ("name", Json::Str(s.name.clone())),
+            ("meaning", Json::Str(s.meaning.clone())),
+        ]),
+        None => Json::Null,
+    };
+
+    let threads = thread_ids(data)
+        .into_iter()
+        .map(|id| {
+            let frames = data
+                .thread_frames
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .map(|f| {
+                    let args = f
+                        .args
+                        .iter()
+                        .flatten()
+                        .map(|(name, t, value)| {
+                            Json::Object(vec![
+                                ("name", Json::Str(name.clone())),
+                                ("type", Json::Str(t.clone())),
+                                ("value", Json::Str(value.clone())),
+                            ])
+                        })
+                        .collect();
+
+                    let locals = data
+                        .frame_variables
+                        .get(&(id.clone(), f.level))
+                        .into_iter()
+                        .flatten()
+                        .filter(|v| !v.arg)
+                        .map(|v| {
+                            Json::Object(vec![
+                                ("name", Json::Str(v.name.clone())),
+                                ("value", Json::opt_str(v.value.as_deref())),
+                            ])
+                        })
+                        .collect();
+
+                    Json::Object(vec![
+                        ("level", Json::Number(f.level as u64)),
+                        ("func", Json::Str(f.func.clone())),
+                        ("addr", f.addr.map(Json::Number).unwrap_or(Json::Null)),
+                        ("file", Json::opt_str(f.file.as_deref())),
+                        (
+                            "line",
+                            f.line.map(|l| Json::Number(l as u64)).unwrap_or(Json::Null),
+                        ),
+                        ("args", Json::Array(args)),
+                        ("locals", Json::Array(locals)),
+                    ])
+                })
+                .collect();
+
+            Json::Object(vec![
+                ("id", Json::Str(id.clone())),
+                ("title", Json::Str(thread_title(data, &id))),
+                (
+                    "crashed",
+                    Json::Bool(data.stopped_thread.as_ref() == Some(&id)),
+                ),
+                ("frames", Json::Array(frames)),
+            ])
+        })
+        .collect();
+
+    let registers = data
+        .registers
+        .iter()
+        .map(|(number, value)| {
+            Json::Object(vec![
+                ("name", Json::Str(register_name(data, *number))),
+                ("value", Json::Str(value.clone())),
+            ])
+        })
+        .collect();
+
+    let pc = faulting_pc(data);
+    let disassembly = data
+        .disassembly
+        .instructions
+        .iter()
+        .map(|i| {
+            Json::Object(vec![
+                ("address", Json::Number(i.address)),
+                ("func", Json::opt_str(i.func.as_deref())),
+                (
+                    "offset",
+                    i.offset
+                        .map(|o| Json::Number(o as u64))
+                        .unwrap_or(Json::Null),
+                ),
+                ("inst", Json::Str(i.inst.clone())),
+                ("current", Json::Bool(Some(i.address) == pc)),
+            ])
+        })
+        .collect();
+
+    Json::Object(vec![
+        ("signal", signal),
+        (
+            "stopped_thread",
+            Json::opt_str(data.stopped_thread.as_deref()),
+        ),
+        ("threads", Json::Array(threads)),
+        ("registers", Json::Array(registers)),
+        ("disassembly", Json::Array(disassembly)),
+        ("memory_map", Json::Str(data.report.memory_map.clone())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembly::Disassembly;
+    use crate::mi_types::{Instruction, Signal, Variable};
+
+    fn crashed() -> PersistentData {
+        let mut data = PersistentData {
+            signal: Some(Signal {
+                name: "SIGSEGV".to_string(),
+                meaning: "Segmentation fault".to_string(),
+            }),
+            stopped_thread: Some("1".to_string()),
+            register_names: vec!["rax".to_string()],
+            registers: vec![(0, "0x1c".to_string())],
+            disassembly: Disassembly {
+                instructions: vec![Instruction {
+                    address: 0x401136,
+                    func: Some("fib".to_string()),
+                    offset: Some(16),
+                    inst: "mov    eax,DWORD PTR [rax]".to_string(),
+                    file: None,
+                    line: None,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        data.thread_frames.insert(
+            "1".to_string(),
+            vec![Frame {
+                addr: Some(0x401136),
+                func: "fib".to_string(),
+                args: Some(vec![("a".into(), "int".into(), "1".into())]),
+                file: Some("example.c".to_string()),
+                fullname: None,
+                line: Some(8),
+                arch: None,
+                level: 0,
+            }],
+        );
+        data.frame_variables.insert(
+            ("1".to_string(), 0),
+            vec![Variable {
+                name: "p".to_string(),
+                var_type: None,
+                value: Some("0x0".to_string()),
+                arg: false,
+            }],
+        );
+        data.report.memory_map = "0x400000 0x401000 r-xp /tmp/a \"out\"\n".to_string();
+
+        data
+    }
+
+    #[test]
+    fn test_markdown() {
+        let md = markdown(&crashed());
+
+        assert!(md.contains("**Signal:** `SIGSEGV` (Segmentation fault)"));
+        assert!(md.contains("#0 0x0000000000401136 in fib(a=1) at example.c:8"));
+        assert!(md.contains("    - `p = 0x0`"));
+        assert!(md.contains("rax      0x1c"));
+        assert!(md.contains("=> 0x0000000000401136 <fib+16>: mov    eax,DWORD PTR [rax]"));
+    }
+
+    #[test]
+    fn test_json() {
+        let json = json(&crashed()).to_string();
+
+        assert!(json.starts_with(
+            r#"{"signal":{"name":"SIGSEGV","meaning":"Segmentation fault"},"stopped_thread":"1","#
+        ));
+        assert!(json.contains(r#""locals":[{"name":"p","value":"0x0"}]"#));
+        assert!(json.contains(r#""memory_map":"0x400000 0x401000 r-xp /tmp/a \"out\"\n""#));
+    }
+}
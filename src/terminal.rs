@@ -0,0 +1,384 @@
+use crate::egui::text::LayoutJob;
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Size the program is told the terminal has
+pub const WIDTH: usize = 80;
+pub const HEIGHT: usize = 24;
+/// Lines kept above the screen
+const SCROLLBACK: usize = 1000;
+
+/// A character on the screen and the ANSI colour (0 to 15) it's drawn with, `None` is the default
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Option<u8>,
+    pub bold: bool,
+}
+
+/// Where we are in an escape sequence
+#[derive(Clone, PartialEq, Debug)]
+enum Escape {
+    None,
+    Esc,
+    /// The parameters of `ESC [`, as in `1;31`
+    Csi(String),
+    /// `ESC (` and `ESC )` pick a character set, we skip the one character that follows
+    Charset,
+}
+
+/// What the program drew, with the subset of VT100/ANSI that line based and simple full screen
+/// programs use: colours, cursor movement and erasing
+#[derive(Clone, PartialEq, Debug)]
+pub struct Screen {
+    /// The scrollback followed by the screen, the last `HEIGHT` lines
+    pub lines: Vec<Vec<Cell>>,
+    pub row: usize,
+    pub col: usize,
+    /// Goes up with every `feed`, the panel lays the screen out again only when it changed
+    pub version: u64,
+    fg: Option<u8>,
+    bold: bool,
+    escape: Escape,
+    /// The start of a UTF-8 character cut by the end of a read
+    partial: Vec<u8>,
+}
+
+impl Default for Screen {
+    fn default() -> Screen {
+        Screen {
+            lines: vec![vec![]],
+            row: 0,
+            col: 0,
+            version: 0,
+            fg: None,
+            bold: false,
+            escape: Escape::None,
+            partial: vec![],
+        }
+    }
+}
+
+impl Screen {
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.version += 1;
+        self.partial.extend_from_slice(bytes);
+        let data = std::mem::take(&mut self.partial);
+
+        let valid = match std::str::from_utf8(&data) {
+            Ok(s) => s.len(),
+            // Incomplete at the end, the rest comes with the next read
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => data.len(),
+        };
+
+        for ch in String::from_utf8_lossy(&data[..valid]).chars() {
+            self.input(ch);
+        }
+        self.partial = data[valid..].to_vec();
+    }
+
+    fn input(&mut self, ch: char) {
+        match std::mem::replace(&mut self.escape, Escape::None) {
+            Escape::Esc => match ch {
+                '[' => self.escape = Escape::Csi(String::new()),
+                '(' | ')' => self.escape = Escape::Charset,
+                _ => {}
+            },
+            Escape::Csi(mut params) => {
+                if ch.is_ascii_digit() || ch == ';' || ch == '?' {
+                    params.push(ch);
+                    self.escape = Escape::Csi(params);
+                } else {
+                    self.csi(&params, ch);
+                }
+            }
+            Escape::Charset => {}
+            Escape::None => match ch {
+                '\x1b' => self.escape = Escape::Esc,
+                '\n' => self.line_feed(),
+                '\r' => self.col = 0,
+                '\x08' => self.col = self.col.saturating_sub(1),
+                '\t' => self.col = ((self.col / 8 + 1) * 8).min(WIDTH - 1),
+                c if c.is_control() => {}
+                c => self.put(c),
+            },
+        }
+    }
+
+    fn put(&mut self, ch: char) {
+        if self.col >= WIDTH {
+            self.col = 0;
+            self.line_feed();
+        }
+
+        let cell = Cell {
+            ch,
+            fg: self.fg,
+            bold: self.bold,
+        };
+        let line = &mut self.lines[self.row];
+        if line.len() <= self.col {
+            line.resize(self.col + 1, Cell::default());
+        }
+        line[self.col] = cell;
+        self.col += 1;
+    }
+
+    fn line_feed(&mut self) {
+        self.row += 1;
+        if self.row == self.lines.len() {
+            self.lines.push(vec![]);
+        }
+
+        let extra = self.lines.len().saturating_sub(SCROLLBACK + HEIGHT);
+        if extra > 0 {
+            self.lines.drain(..extra);
+            self.row -= extra;
+        }
+    }
+
+    /// The first line of the screen, cursor positions are relative to it
+    fn top(&self) -> usize {
+        self.lines.len().saturating_sub(HEIGHT)
+    }
+
+    fn csi(&mut self, params: &str, action: char) {
+        // Private modes, like hiding the cursor, don't change what's drawn
+        if params.starts_with('?') {
+            return;
+        }
+
+        let nums = params
+            .split(';')
+            .map(|p| p.parse::<usize>().ok())
+            .collect::<Vec<_>>();
+        let n = |i: usize, default: usize| nums.get(i).copied().flatten().unwrap_or(default);
+
+        match action {
+            'm' => self.sgr(&nums),
+            'A' => self.row = self.row.saturating_sub(n(0, 1)).max(self.top()),
+            'B' => self.goto_row(self.row - self.top() + n(0, 1)),
+            'C' => self.col = (self.col + n(0, 1)).min(WIDTH - 1),
+            'D' => self.col = self.col.saturating_sub(n(0, 1)),
+            'G' => self.col = n(0, 1).clamp(1, WIDTH) - 1,
+            'H' | 'f' => {
+                self.goto_row(n(0, 1).clamp(1, HEIGHT) - 1);
+                self.col = n(1, 1).clamp(1, WIDTH) - 1;
+            }
+            'J' => match n(0, 0) {
+                0 => {
+                    self.lines[self.row].truncate(self.col);
+                    self.lines.truncate(self.row + 1);
+                }
+                // The scrollback is kept
+                2 | 3 => {
+                    let top = self.top();
+                    for line in &mut self.lines[top..] {
+                        line.clear();
+                    }
+                }
+                _ => {}
+            },
+            'K' => {
+                let col = self.col;
+                let line = &mut self.lines[self.row];
+                match n(0, 0) {
+                    0 => line.truncate(col),
+                    1 => line
+                        .iter_mut()
+                        .take(col + 1)
+                        .for_each(|c| *c = Cell::default()),
+                    2 => line.clear(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves to `row` of the screen, making the lines up to it if the program didn't print them
+    fn goto_row(&mut self, row: usize) {
+        let row = self.top() + row.min(HEIGHT - 1);
+        while self.lines.len() <= row {
+            self.lines.push(vec![]);
+        }
+        self.row = row;
+    }
+
+    fn sgr(&mut self, nums: &[Option<usize>]) {
+        let mut nums = nums.iter().map(|n| n.unwrap_or(0));
+
+        while let Some(n) = nums.next() {
+            match n {
+                0 => {
+                    self.fg = None;
+                    self.bold = false;
+                }
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.fg = Some((n - 30) as u8),
+                90..=97 => self.fg = Some((n - 90 + 8) as u8),
+                39 => self.fg = None,
+                // 256 colours, the first 16 are the usual ones
+                38 if nums.next() == Some(5) => {
+                    self.fg = nums.next().filter(|c| *c < 16).map(|c| c as u8);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell {
+            ch: ' ',
+            fg: None,
+            bold: false,
+        }
+    }
+}
+
+/// The terminal panel
+#[derive(Default)]
+pub struct TerminalView {
+    /// Keys go to the program while the panel has the focus
+    pub focused: bool,
+    /// The screen as laid out last, with the `Screen::version` and focus it was laid out for
+    pub job: Option<(u64, bool, LayoutJob)>,
+}
+
+/// The pseudo-terminal the program runs in, GDB is told about it with `-inferior-tty-set`
+pub struct Pty {
+    /// `/dev/pts/N`
+    pub name: String,
+    pub screen: Arc<Mutex<Screen>>,
+    master: File,
+    /// Kept open so reading the master doesn't fail between runs, when no program has the
+    /// terminal open
+    _slave: File,
+}
+
+impl Pty {
+    /// `on_output` is called after what the program wrote is on the screen
+    pub fn open(on_output: impl Fn() + Send + 'static) -> io::Result<Pty> {
+        let master = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            File::from_raw_fd(fd)
+        };
+
+        let fd = master.as_raw_fd();
+        let name = unsafe {
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut buf = [0 as libc::c_char; 128];
+            if libc::ptsname_r(fd, buf.as_mut_ptr(), buf.len()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let size = libc::winsize {
+                ws_row: HEIGHT as u16,
+                ws_col: WIDTH as u16,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+            libc::ioctl(fd, libc::TIOCSWINSZ, &size);
+
+            CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+        };
+
+        let slave = OpenOptions::new().read(true).write(true).open(&name)?;
+
+        let screen = Arc::new(Mutex::new(Screen::default()));
+        let mut reader = master.try_clone()?;
+        let reader_screen = screen.clone();
+        thread::spawn(move || {
+            let mut buf = [0; 4096];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                reader_screen.lock().unwrap().feed(&buf[..n]);
+                on_output();
+            }
+        });
+
+        Ok(Pty {
+            name,
+            screen,
+            master,
+            _slave: slave,
+        })
+    }
+
+    /// What was typed in the terminal panel, the line discipline echoes it
+    pub fn send(&self, bytes: &[u8]) {
+        let _ = (&self.master).write_all(bytes);
+    }
+
+    pub fn command(&self) -> String {
+        format!("-inferior-tty-set {}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(screen: &Screen) -> Vec<String> {
+        screen
+            .lines
+            .iter()
+            .map(|l| l.iter().map(|c| c.ch).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut screen = Screen::default();
+
+        screen.feed(b"Name: \x1b[1;31merror\x1b[0m ok\r\n");
+        assert_eq!(text(&screen), vec!["Name: error ok", ""]);
+        assert_eq!(screen.lines[0][6].fg, Some(1));
+        assert!(screen.lines[0][6].bold);
+        assert_eq!(screen.lines[0][12].fg, None);
+
+        // A progress bar redrawn in place
+        screen.feed(b"10%\r20%\x1b[K\r\n");
+        assert_eq!(text(&screen)[1], "20%");
+
+        // "é" cut in half by the end of a read
+        screen.feed(b"caf\xc3");
+        screen.feed(b"\xa9\x08\x08X");
+        assert_eq!(text(&screen)[2], "caXé");
+
+        screen.feed(b"\x1b[2J\x1b[1;5Htop\x1b[?25l");
+        assert_eq!(text(&screen), vec!["    top", "", ""]);
+        assert_eq!((screen.row, screen.col), (0, 7));
+    }
+
+    #[test]
+    fn test_scrollback() {
+        let mut screen = Screen::default();
+        for i in 0..SCROLLBACK + HEIGHT + 10 {
+            screen.feed(format!("{i}\r\n").as_bytes());
+        }
+
+        assert_eq!(screen.lines.len(), SCROLLBACK + HEIGHT);
+        assert_eq!(screen.row, screen.lines.len() - 1);
+        assert_eq!(text(&screen)[0], "11");
+
+        screen.feed(b"\x1b[Hfirst");
+        assert_eq!(text(&screen)[screen.top()], "first");
+    }
+}
@@ -0,0 +1,65 @@
+use std::ops::Range;
+
+// Every token we put in front of a command, GDB repeats it in front of the result record (see
+// `mi::token`). They are all here so no two kinds of answers share a number: `process_output`
+// hands every token to every module, each one only takes its own.
+
+/// `-thread-list-ids` that starts the walk ending with a crash report
+pub const REPORT: u64 = 1;
+/// `-target-attach`, its error goes back to the attach dialog
+pub const ATTACH: u64 = 2;
+/// Reads of the memory view
+pub const MEMORY: u64 = 3;
+/// The disassembly of the whole function, and of a range when there's no function
+pub const DISASSEMBLE_FUNCTION: u64 = 4;
+pub const DISASSEMBLE_RANGE: u64 = 5;
+/// `-gdb-show non-stop`, so we know GDB took it
+pub const NON_STOP: u64 = 6;
+/// `-complete`, the answer goes to the command line
+pub const COMPLETE: u64 = 7;
+
+/// The settings of `inferiors::SETTINGS`, by index
+pub const INFERIOR_SETTINGS: Range<u64> = 10..1_000;
+/// Evaluations of the watches and the watchpoints made out of them, by watch id
+pub const WATCH_VALUES: Range<u64> = 1_000_000..2_000_000;
+pub const WATCHPOINTS: Range<u64> = 2_000_000..3_000_000;
+/// Values and types of the REPL entries
+pub const REPL_VALUES: Range<u64> = 3_000_000..4_000_000;
+pub const REPL_TYPES: Range<u64> = 4_000_000..5_000_000;
+
+/// The token for item `n` of `range`. It wraps around, an answer that old is long gone.
+pub fn nth(range: Range<u64>, n: u64) -> u64 {
+    range.start + n % (range.end - range.start)
+}
+
+/// Which item of `range` `token` is for, `None` if it isn't one of its tokens
+pub fn index(range: Range<u64>, token: Option<u64>) -> Option<u64> {
+    let token = token?;
+    range.contains(&token).then(|| token - range.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        let ranges = [
+            INFERIOR_SETTINGS,
+            WATCH_VALUES,
+            WATCHPOINTS,
+            REPL_VALUES,
+            REPL_TYPES,
+        ];
+        for (i, a) in ranges.iter().enumerate() {
+            assert!(a.start > COMPLETE);
+            for b in &ranges[i + 1..] {
+                assert!(a.end <= b.start);
+            }
+        }
+
+        assert_eq!(index(WATCHPOINTS, Some(nth(WATCHPOINTS, 3))), Some(3));
+        assert_eq!(index(WATCHPOINTS, Some(nth(REPL_VALUES, 3))), None);
+        assert_eq!(index(WATCHPOINTS, None), None);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::mi::{self, Output};
+use crate::mi_types::MIResult;
+
+/// Blocks kept, the oldest ones go first
+const MAX_BLOCKS: usize = 2000;
+
+/// What kind of line GDB sent, the console can show or hide each kind
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stream {
+    /// `~`, the answer of CLI commands
+    Console,
+    /// `@`, what a remote target printed
+    Target,
+    /// `&`, GDB's own messages and the CLI command being run
+    Log,
+    /// `^done`, `^running`...
+    Result,
+    /// `^error`
+    Error,
+    /// `*stopped`, `=thread-created`...
+    Async,
+    /// Anything that isn't MI, like the shell's output before GDB starts
+    Other,
+}
+
+pub const STREAMS: &[(Stream, &str)] = &[
+    (Stream::Console, "Console"),
+    (Stream::Target, "Target"),
+    (Stream::Log, "Log"),
+    (Stream::Result, "Results"),
+    (Stream::Error, "Errors"),
+    (Stream::Async, "Async"),
+    (Stream::Other, "Other"),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Record {
+    pub stream: Stream,
+    /// What's shown, the unescaped text of stream records and the message of errors
+    pub text: String,
+    /// The line as GDB sent it
+    pub raw: String,
+}
+
+/// A command and everything GDB answered to it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Block {
+    /// `None` for what GDB says by itself, like `*stopped` after a `-exec-continue` was answered
+    pub command: Option<String>,
+    pub records: Vec<Record>,
+    /// Got its result record
+    pub done: bool,
+}
+
+/// Everything said between us and GDB, as command blocks
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Transcript {
+    pub blocks: Vec<Block>,
+}
+
+/// Which records the console shows and how
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConsoleView {
+    /// Same order as `STREAMS`
+    pub shown: Vec<bool>,
+    pub search: String,
+    pub raw: bool,
+}
+
+impl Default for ConsoleView {
+    fn default() -> ConsoleView {
+        ConsoleView {
+            // Async records are mostly for us, there are many of them
+            shown: STREAMS.iter().map(|(s, _)| *s != Stream::Async).collect(),
+            search: String::new(),
+            raw: false,
+        }
+    }
+}
+
+impl ConsoleView {
+    pub fn shows(&self, stream: Stream) -> bool {
+        STREAMS
+            .iter()
+            .zip(&self.shown)
+            .any(|((s, _), shown)| *s == stream && *shown)
+    }
+
+    /// The records of `block` to show, `None` if the block doesn't match the search
+    pub fn visible<'a>(&self, block: &'a Block) -> Option<Vec<&'a Record>> {
+        let records = block
+            .records
+            .iter()
+            .filter(|r| self.shows(r.stream))
+            .collect::<Vec<_>>();
+
+        if block.command.is_none() && records.is_empty() {
+            return None;
+        }
+
+        let search = self.search.trim().to_lowercase();
+        if search.is_empty() {
+            return Some(records);
+        }
+
+        let in_command = block
+            .command
+            .as_ref()
+            .is_some_and(|c| c.to_lowercase().contains(&search));
+        if in_command {
+            return Some(records);
+        }
+
+        let matching = records
+            .into_iter()
+            .filter(|r| self.text(r).to_lowercase().contains(&search))
+            .collect::<Vec<_>>();
+        (!matching.is_empty()).then_some(matching)
+    }
+
+    pub fn text<'a>(&self, record: &'a Record) -> &'a str {
+        if self.raw {
+            &record.raw
+        } else {
+            &record.text
+        }
+    }
+}
+
+impl Transcript {
+    /// GDB itself is started from a shell, those commands get no result record and neither do
+    /// empty lines
+    pub fn sent(&mut self, command: &str) {
+        let shell = command.starts_with("gdb ") || command.starts_with("which ");
+
+        // A new GDB, what the old one didn't answer never will be
+        if command.starts_with("gdb ") {
+            for block in &mut self.blocks {
+                block.done = true;
+            }
+        }
+
+        self.push(Block {
+            command: Some(command.to_string()),
+            records: vec![],
+            done: shell || command.trim().is_empty(),
+        });
+    }
+
+    /// Something we say ourselves, like a process that didn't start
+    pub fn note(&mut self, text: &str) {
+        self.push(Block {
+            command: None,
+            records: vec![Record {
+                stream: Stream::Other,
+                text: text.to_string(),
+                raw: text.to_string(),
+            }],
+            done: true,
+        });
+    }
+
+    /// GDB answers commands in order, so what it sends goes to the oldest command without a
+    /// result yet, and a result record to the command with its token. With none waiting it's GDB
+    /// talking by itself.
+    pub fn received(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim() == "(gdb)" || line.is_empty() {
+            return;
+        }
+
+        let record = classify(line);
+        let result = matches!(record.stream, Stream::Result | Stream::Error);
+
+        let waiting = if result {
+            self.answered(mi::token(line))
+        } else {
+            self.blocks.iter().position(|b| !b.done)
+        };
+        let block = match waiting {
+            Some(i) => &mut self.blocks[i],
+            None => {
+                let by_itself = self.blocks.last().is_some_and(|b| {
+                    b.command.is_none() || matches!(&b.command, Some(c) if c.starts_with("gdb "))
+                });
+                if !by_itself {
+                    self.push(Block {
+                        command: None,
+                        records: vec![],
+                        done: true,
+                    });
+                }
+                self.blocks.last_mut().unwrap()
+            }
+        };
+
+        block.records.push(record);
+        if result {
+            block.done = true;
+        }
+    }
+
+    /// The waiting block whose command has `token`, or the oldest one. The ones sent before it
+    /// are never answered, as GDB answers in order, so they stop waiting.
+    fn answered(&mut self, token: Option<u64>) -> Option<usize> {
+        let i = self
+            .blocks
+            .iter()
+            .position(|b| !b.done && b.command.as_deref().and_then(command_token) == token)
+            .or_else(|| self.blocks.iter().position(|b| !b.done))?;
+
+        for block in &mut self.blocks[..i] {
+            block.done = true;
+        }
+
+        Some(i)
+    }
+
+    fn push(&mut self, block: Block) {
+        self.blocks.push(block);
+
+        let extra = self.blocks.len().saturating_sub(MAX_BLOCKS);
+        self.blocks.drain(..extra);
+    }
+}
+
+/// The number in front of a command, see `mi::token`
+fn command_token(command: &str) -> Option<u64> {
+    let end = command.find(|c: char| !c.is_ascii_digit())?;
+    command[..end].parse().ok()
+}
+
+fn classify(line: &str) -> Record {
+    let record = |stream, text: String| Record {
+        stream,
+        text,
+        raw: line.to_string(),
+    };
+
+    match mi::parse_stream(line) {
+        Ok((_, Output::ConsoleStream(s))) => record(Stream::Console, s),
+        Ok((_, Output::TargetStream(s))) => record(Stream::Target, s),
+        Ok((_, Output::LogStream(s))) => record(Stream::Log, s),
+        Ok((_, Output::ResultRecord(MIResult::Error { msg, .. }, _))) => record(Stream::Error, msg),
+        Ok((_, Output::ResultRecord(..))) => record(Stream::Result, result_text(line)),
+        Ok((_, Output::ExecAsync(..) | Output::NotifyAsync(..) | Output::StatusAsync)) => {
+            record(Stream::Async, line.to_string())
+        }
+        // Records we don't parse, still MI if they start like one
+        Err(_) => {
+            let symbol = line
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .chars()
+                .next();
+            match symbol {
+                Some('^') if line.contains("^error") => record(Stream::Error, line.to_string()),
+                Some('^') => record(Stream::Result, result_text(line)),
+                Some('*' | '=' | '+') => record(Stream::Async, line.to_string()),
+                _ => record(Stream::Other, line.to_string()),
+            }
+        }
+    }
+}
+
+/// `^done,value="3"` without the token
+fn result_text(line: &str) -> String {
+    line.trim_start_matches(|c: char| c.is_ascii_digit())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let mut t = Transcript::default();
+
+        t.sent("gdb --interpreter=mi3");
+        t.received("=thread-group-added,id=\"i1\"\n");
+        t.received("(gdb)\n");
+        t.sent("-gdb-set mi-async on");
+        t.sent("info line");
+        t.received("^done\n");
+        t.received("&\"info line\\n\"\n");
+        t.received("~\"Line 5 of \\\"a.c\\\"\\n\"\n");
+        t.received("^done\n");
+        t.received("*stopped,reason=\"end-stepping-range\"\n");
+        t.sent("4-data-disassemble -a \"$pc\" -- 4");
+        t.received("4^error,msg=\"No function contains specified address.\"\n");
+        t.note("Failed to start gdbserver");
+
+        let streams = |b: &Block| b.records.iter().map(|r| r.stream).collect::<Vec<_>>();
+        assert_eq!(t.blocks.len(), 6);
+        assert_eq!(streams(&t.blocks[0]), vec![Stream::Async]);
+        assert_eq!(streams(&t.blocks[1]), vec![Stream::Result]);
+        assert_eq!(
+            streams(&t.blocks[2]),
+            vec![Stream::Log, Stream::Console, Stream::Result]
+        );
+        assert_eq!(t.blocks[2].records[1].text, "Line 5 of \"a.c\"\n");
+        assert_eq!(t.blocks[3].command, None);
+        assert_eq!(t.blocks[4].records[0].stream, Stream::Error);
+        assert_eq!(
+            t.blocks[4].records[0].text,
+            "No function contains specified address."
+        );
+        assert!(t.blocks.iter().all(|b| b.done));
+    }
+
+    #[test]
+    fn test_unanswered() {
+        let mut t = Transcript::default();
+
+        t.sent("gdb --interpreter=mi3");
+        t.sent("-exec-continue");
+        // The GDB started by "Reload" doesn't answer what the old one was sent
+        t.sent("gdb --interpreter=mi3");
+        t.sent("");
+        t.sent("-gdb-set mi-async on");
+        t.sent("7-complete \"br\"");
+        t.sent("info line");
+        t.received("7^done,completion=\"break\"\n");
+        t.received("~\"Line 5\\n\"\n");
+        t.received("^done\n");
+
+        assert!(t.blocks.iter().all(|b| b.done));
+        assert_eq!(t.blocks[1].records, vec![]);
+        assert_eq!(t.blocks[4].records, vec![]);
+        assert_eq!(t.blocks[5].records.len(), 1);
+        assert_eq!(t.blocks[6].records.len(), 2);
+    }
+
+    #[test]
+    fn test_view() {
+        let mut t = Transcript::default();
+        t.sent("-gdb-set mi-async on");
+        t.received("^done\n");
+        t.sent("info line");
+        t.received("~\"Line 5 of \\\"a.c\\\"\\n\"\n");
+        t.received("^done\n");
+        t.received("=breakpoint-modified,bkpt={number=\"1\"}\n");
+
+        let mut view = ConsoleView::default();
+        // The notification block has nothing to show
+        assert_eq!(view.visible(&t.blocks[2]), None);
+
+        view.search = "LINE 5".into();
+        assert_eq!(view.visible(&t.blocks[0]), None);
+        assert_eq!(view.visible(&t.blocks[1]).map(|r| r.len()), Some(1));
+
+        view.raw = true;
+        view.search = "~\"Line".into();
+        assert_eq!(view.visible(&t.blocks[1]).map(|r| r.len()), Some(1));
+    }
+}
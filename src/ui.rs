@@ -1,13 +1,42 @@
-use crate::control::{ControlState, PersistentData};
-use crate::egui::Color32;
-use eframe::egui::{RichText, Ui};
+use crate::command_line::CommandLine;
+use crate::control::{ControlState, GDBExecutionState, PersistentData};
+use crate::disassembly::{self, DisassemblyEdit};
+use crate::egui::{self, Color32};
+use crate::inferiors;
+use crate::kernel;
+use crate::memory::{self, MemoryEdit, MemoryView};
+use crate::mi_types::{ExitStatus, Thread};
+use crate::procs;
+use crate::registers::{self, Group, RegisterView};
+use crate::repl::{self, ReplEdit, ReplView, Value};
+use crate::terminal;
+use crate::transcript::{ConsoleView, Stream, Transcript, STREAMS};
+use crate::watch::WatchEdit;
+use eframe::egui::{Label, RichText, Sense, Ui};
 use std::fs;
 
-pub fn current_file(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
+/// Clicking a line runs the program up to it
+pub fn current_file(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+) -> Option<ControlState> {
+    let mut next = None;
+
     match (state, data) {
-        (ControlState::GDBRunning { .. }, PersistentData { file: Some(p), line: Some(cur_line), .. }) => {
-            // We don't have the responsibility to handle an incorrect path here
-            let contents = fs::read_to_string(p).unwrap();
+        (
+            ControlState::GDBRunning { .. },
+            PersistentData {
+                file: Some(p),
+                line: Some(cur_line),
+                ..
+            },
+        ) => {
+            // A path from another machine, `asm_only` shows the instructions instead
+            let contents = match fs::read_to_string(p) {
+                Ok(contents) => contents,
+                Err(_) => return None,
+            };
             for (i, line) in contents.lines().enumerate() {
                 let color = if i + 1 == (*cur_line) as usize {
                     Color32::from_rgb(255, 155, 155)
@@ -17,26 +46,1470 @@ pub fn current_file(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
 
                 let line = format!("{:02} {}", i + 1, line);
 
-                ui.monospace(RichText::new(line).color(color));
+                let label =
+                    Label::new(RichText::new(line).monospace().color(color)).sense(Sense::click());
+
+                // There's nothing to run when looking at a core file
+                if ui.add(label).on_hover_text("Run to this line").clicked()
+                    && state.button_enabled("Until")
+                {
+                    next = Some(ControlState::run_to_line(
+                        p,
+                        (i + 1) as u32,
+                        data.disassembly.hardware,
+                    ));
+                }
             }
         }
         _ => {}
     }
+
+    next
 }
 
-pub fn stack_frame(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
-    match (state, data) {
+/// Instructions with the source lines they come from, when there's source. `=>` marks the PC of the
+/// selected frame, clicking the gutter sets or deletes a breakpoint and clicking a jump or call
+/// target disassembles it.
+pub fn disassembly(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+) -> Option<DisassemblyEdit> {
+    // Without source it's already the main view, see `asm_only`
+    if !matches!(state, ControlState::GDBRunning { .. }) || data.asm_only() {
+        return None;
+    }
+
+    ui.collapsing("Disassembly", |ui| disassembly_listing(ui, state, data))
+        .body_returned
+        .flatten()
+}
+
+/// What we show instead of the source file when there's none: the instructions around the PC
+pub fn asm_only(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+) -> Option<DisassemblyEdit> {
+    if !matches!(state, ControlState::GDBRunning { .. }) || !data.asm_only() {
+        return None;
+    }
+
+    let location = match &data.file {
+        Some(file) => format!("{} isn't here", file.display()),
+        None => "No debug information".to_string(),
+    };
+    ui.label(format!("{location}, stepping goes by instruction"));
+
+    disassembly_listing(ui, state, data)
+}
+
+fn disassembly_listing(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+) -> Option<DisassemblyEdit> {
+    let mut edit = None;
+    let d = &data.disassembly;
+    let pc = data.frames.as_ref().and_then(|frames| {
+        let level = data.selected_frame.unwrap_or(0);
+        frames.iter().find(|f| f.level == level)?.addr
+    });
+    if let Some(at) = &d.at {
+        ui.horizontal(|ui| {
+            ui.monospace(at);
+            if ui.button("Back to the PC").clicked() {
+                edit = Some(DisassemblyEdit::FollowPc);
+            }
+        });
+    }
+
+    if let Some(e) = &d.error {
+        ui.colored_label(Color32::from_rgb(255, 100, 100), e);
+    }
+
+    let mut last_line = None;
+    egui::Grid::new("disassembly").show(ui, |ui| {
+        for i in &d.instructions {
+            if let (Some(file), Some(line)) = (&i.file, i.line) {
+                if last_line != Some((file, line)) {
+                    let text = d.source_line(file, line);
+
+                    ui.label("");
+                    ui.label(
+                        RichText::new(format!("{line:>5} {text}"))
+                            .monospace()
+                            .color(Color32::from_rgb(120, 170, 220)),
+                    );
+                    ui.end_row();
+                }
+                last_line = Some((file, line));
+            }
+
+            if let Some(e) = instruction_row(ui, state, d, i, pc) {
+                edit = Some(e);
+            }
+            ui.end_row();
+        }
+    });
+
+    edit
+}
+
+fn instruction_row(
+    ui: &mut Ui,
+    state: &ControlState,
+    d: &disassembly::Disassembly,
+    i: &crate::mi_types::Instruction,
+    pc: Option<u64>,
+) -> Option<DisassemblyEdit> {
+    let mut edit = None;
+    // Breakpoints can only be changed while the inferior is stopped
+    let stopped = state.is_stopped();
+
+    let breakpoint = d.breakpoint_at(i.address);
+    let gutter = match (breakpoint, Some(i.address) == pc) {
+        (Some(_), true) => "*=>",
+        (Some(_), false) => "*  ",
+        (None, true) => " =>",
+        (None, false) => "   ",
+    };
+    let gutter = Label::new(
+        RichText::new(gutter)
+            .monospace()
+            .color(Color32::from_rgb(255, 100, 100)),
+    )
+    .sense(Sense::click());
+    let hover = if breakpoint.is_some() {
+        "Delete the breakpoint"
+    } else {
+        "Set a breakpoint here"
+    };
+    if ui.add(gutter).on_hover_text(hover).clicked() && stopped {
+        edit = Some(match breakpoint {
+            Some(n) => DisassemblyEdit::Delete(n.to_string()),
+            None => DisassemblyEdit::Break(i.address),
+        });
+    }
+
+    let color = if Some(i.address) == pc {
+        Color32::from_rgb(255, 155, 155)
+    } else {
+        Color32::from_rgb(150, 150, 150)
+    };
+    let symbol = match (&i.func, i.offset) {
+        (Some(func), Some(offset)) => format!(" <{func}+{offset}>"),
+        _ => String::new(),
+    };
+
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(format!("{:#018x}{symbol}:", i.address))
+                .monospace()
+                .color(color),
+        );
+
+        match disassembly::branch_target(&i.inst) {
+            Some((target, name)) => {
+                let mnemonic = i.inst.split("0x").next().unwrap_or_default();
+                ui.label(RichText::new(mnemonic).monospace().color(color));
+                let text = match name {
+                    Some(name) => format!("{target:#x} <{name}>"),
+                    None => format!("{target:#x}"),
+                };
+                let link = Label::new(
+                    RichText::new(text)
+                        .monospace()
+                        .underline()
+                        .color(Color32::from_rgb(120, 170, 220)),
+                )
+                .sense(Sense::click());
+                if ui.add(link).on_hover_text("Disassemble it").clicked() {
+                    edit = Some(DisassemblyEdit::Goto(target));
+                }
+            }
+            None => {
+                ui.label(RichText::new(&i.inst).monospace().color(color));
+            }
+        }
+    });
+
+    edit
+}
+
+pub fn return_value(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
+    if let (
+        ControlState::GDBRunning { .. },
+        PersistentData {
+            return_value: Some(v),
+            ..
+        },
+    ) = (state, data)
+    {
+        ui.monospace(format!("Value returned: {v}"));
+    }
+}
+
+/// The call stack of the selected thread, clicking a frame selects it
+pub fn stack_frame(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+) -> Option<ControlState> {
+    let frames = match (state, &data.frames) {
+        (ControlState::GDBRunning { .. }, Some(fs)) => fs,
+        _ => return None,
+    };
+
+    // Frames can't be selected while the target runs
+    let selectable = !matches!(
+        state,
+        ControlState::GDBRunning {
+            state: GDBExecutionState::Running,
+            ..
+        }
+    );
+
+    let mut next = None;
+
+    // Without source this and the registers are all there is to go by
+    egui::CollapsingHeader::new("Call stack")
+        .default_open(data.asm_only())
+        .show(ui, |ui| {
+            egui::Grid::new("call stack").striped(true).show(ui, |ui| {
+                for f in frames {
+                    let selected = data.selected_frame.unwrap_or(0) == f.level;
+                    let location = match (&f.file, f.line) {
+                        (Some(file), Some(line)) => format!("{file}:{line}"),
+                        _ => "??".to_string(),
+                    };
+                    let addr = f.addr.map(|a| format!("{a:#018x}")).unwrap_or_default();
+
+                    let level = ui.add_enabled(
+                        selectable,
+                        egui::SelectableLabel::new(selected, format!("#{}", f.level)),
+                    );
+                    let func = ui.add_enabled(
+                        selectable,
+                        egui::SelectableLabel::new(selected, RichText::new(&f.func).monospace()),
+                    );
+                    ui.monospace(location);
+                    ui.monospace(addr);
+                    ui.end_row();
+
+                    if (level.clicked() || func.clicked()) && !selected {
+                        next = Some(ControlState::select_frame(state, f.level, data));
+                    }
+                }
+            });
+        });
+
+    next
+}
+
+pub fn signal(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
+    if let (
+        ControlState::GDBRunning { .. },
+        PersistentData {
+            signal: Some(s), ..
+        },
+    ) = (state, data)
+    {
+        let msg = format!("Received signal {}, {}", s.name, s.meaning);
+        ui.label(RichText::new(msg).color(Color32::from_rgb(255, 155, 155)));
+    }
+}
+
+/// Locals and arguments of the selected frame, the ones that changed since the last stop stand out.
+/// Structs, arrays and pointers expand into their variable objects' children.
+pub fn variables(ui: &mut Ui, state: &ControlState, data: &PersistentData) -> Option<ControlState> {
+    if !matches!(state, ControlState::GDBRunning { .. }) || data.variables.is_empty() {
+        return None;
+    }
+
+    let mut next = None;
+
+    ui.collapsing("Variables", |ui| {
+        // Until GDB creates the variable objects there's only the flat list
+        if data.varobjs.roots.is_empty() {
+            egui::Grid::new("variables").striped(true).show(ui, |ui| {
+                for v in &data.variables {
+                    ui.monospace(&v.name);
+                    ui.monospace(v.var_type.as_deref().unwrap_or(""));
+                    ui.label(value_text(
+                        v.value.as_deref().unwrap_or("..."),
+                        data.variable_changed(v),
+                    ));
+                    ui.end_row();
+                }
+            });
+            return;
+        }
+
+        for root in &data.varobjs.roots {
+            if let Some(n) = varobj_node(ui, state, data, root) {
+                next = Some(n);
+            }
+        }
+    });
+
+    next
+}
+
+fn value_text(value: &str, changed: bool) -> RichText {
+    let color = if changed {
+        Color32::from_rgb(255, 200, 100)
+    } else {
+        Color32::from_rgb(150, 150, 150)
+    };
+
+    RichText::new(value).monospace().color(color)
+}
+
+/// Children are only listed the first time a node is opened
+fn varobj_node(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    name: &str,
+) -> Option<ControlState> {
+    let node = data.varobjs.nodes.get(name)?;
+    let obj = &node.obj;
+
+    let label = format!(
+        "{}: {}",
+        obj.expression.as_deref().unwrap_or(name),
+        obj.var_type.as_deref().unwrap_or("?")
+    );
+    let value = obj.value.as_deref().unwrap_or("<out of scope>");
+
+    if obj.numchild == 0 {
+        ui.horizontal(|ui| {
+            ui.monospace(label);
+            ui.label(value_text(value, node.changed));
+        });
+        return None;
+    }
+
+    let mut next = None;
+    let header =
+        egui::CollapsingHeader::new(value_text(&format!("{label} = {value}"), node.changed))
+            .id_source(("varobj", name))
+            .show(ui, |ui| match &node.children {
+                Some(children) => {
+                    for child in children {
+                        if let Some(n) = varobj_node(ui, state, data, child) {
+                            next = Some(n);
+                        }
+                    }
+                }
+                None => {
+                    ui.label("...");
+                }
+            });
+
+    if header.header_response.clicked() && node.children.is_none() {
+        next = Some(ControlState::list_children(state, name));
+    }
+
+    next
+}
+
+/// The watch list with each value or error. A new watch offers to become a watchpoint, `offer` is
+/// the index of the one that does.
+pub fn watches(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    input: &mut String,
+    offer: &mut Option<usize>,
+) -> Option<WatchEdit> {
+    if !matches!(state, ControlState::GDBRunning { .. }) {
+        return None;
+    }
+
+    let mut edit = None;
+
+    ui.collapsing("Watches", |ui| {
+        egui::Grid::new("watches").striped(true).show(ui, |ui| {
+            for (i, w) in data.watches.iter().enumerate() {
+                ui.monospace(&w.expression);
+                match &w.value {
+                    Some(Ok(v)) => ui.monospace(v),
+                    Some(Err(e)) => ui.colored_label(Color32::from_rgb(255, 100, 100), e),
+                    None => ui.monospace("..."),
+                };
+                match &w.watchpoint {
+                    Some(Ok(n)) => {
+                        ui.monospace(format!("watchpoint {n}"));
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::from_rgb(255, 100, 100), e);
+                    }
+                    None => {
+                        if ui.button("Watchpoint").clicked() {
+                            edit = Some(WatchEdit::Watchpoint(i));
+                        }
+                    }
+                }
+                if ui.button("Remove").clicked() {
+                    edit = Some(WatchEdit::Remove(i));
+                    *offer = None;
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(w) = offer.and_then(|i| data.watches.get(i)) {
+            ui.horizontal(|ui| {
+                ui.label(format!("Stop whenever `{}` changes?", w.expression));
+                if ui.button("Set a hardware watchpoint").clicked() {
+                    edit = offer.map(WatchEdit::Watchpoint);
+                    *offer = None;
+                }
+                if ui.button("No").clicked() {
+                    *offer = None;
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(input)
+                    .hint_text("Expression")
+                    .font(egui::TextStyle::Monospace),
+            );
+            if ui.button("Add watch").clicked() && !input.trim().is_empty() {
+                edit = Some(WatchEdit::Add(std::mem::take(input)));
+                *offer = Some(data.watches.len());
+            }
+        });
+    });
+
+    edit
+}
+
+/// Registers by group. The format button next to a value cycles through hex, decimal, binary and
+/// float, and clicking the value edits it.
+pub fn registers(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    view: &mut RegisterView,
+) -> Option<ControlState> {
+    if !matches!(state, ControlState::GDBRunning { .. }) || data.registers.is_empty() {
+        return None;
+    }
+
+    let mut next = None;
+
+    egui::CollapsingHeader::new("Registers")
+        .default_open(data.asm_only())
+        .show(ui, |ui| {
+            for group in registers::GROUPS {
+                let regs = data
+                    .registers
+                    .iter()
+                    .filter_map(|(n, v)| Some((*n, data.register_names.get(*n as usize)?, v)))
+                    .filter(|(_, name, _)| !name.is_empty() && Group::of(name) == *group)
+                    .collect::<Vec<_>>();
+                if regs.is_empty() {
+                    continue;
+                }
+
+                egui::CollapsingHeader::new(group.title())
+                    .default_open(*group == Group::General)
+                    .show(ui, |ui| {
+                        egui::Grid::new(("registers", group.title()))
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (number, name, value) in regs {
+                                    let row =
+                                        register_row(ui, state, data, view, number, name, value);
+                                    if let Some(n) = row {
+                                        next = Some(n);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+        });
+
+    next
+}
+
+fn register_row(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    view: &mut RegisterView,
+    number: u32,
+    name: &str,
+    value: &str,
+) -> Option<ControlState> {
+    let format = view.formats.get(&number).copied().unwrap_or_default();
+    let mut next = None;
+
+    ui.monospace(name);
+
+    match &mut view.editing {
+        Some((n, input)) if *n == number => {
+            let res = ui.add(egui::TextEdit::singleline(input).font(egui::TextStyle::Monospace));
+
+            // Enter writes it, clicking somewhere else gives up
+            if res.lost_focus() {
+                let parsed = registers::parse_input(input, format)
+                    .filter(|_| ui.input().key_pressed(egui::Key::Enter));
+                if let Some(v) = parsed {
+                    next = Some(ControlState::write_register(state, name, &v));
+                }
+                view.editing = None;
+            } else {
+                res.request_focus();
+            }
+        }
+        _ => {
+            let shown = registers::format(value, format);
+            let label = Label::new(value_text(&shown, data.register_changed(number, value)))
+                .sense(Sense::click());
+
+            // There's nothing to write to in a core file, and nothing to read while running
+            if ui.add(label).clicked() && state.is_stopped() {
+                view.editing = Some((number, shown));
+            }
+        }
+    }
+
+    if ui.small_button(format.label()).clicked() {
+        view.formats.insert(number, format.next());
+    }
+
+    next
+}
+
+/// A page of memory in hex and ASCII, read again on every stop. Bytes are grouped by 1, 2, 4 or 8
+/// and a group is shown as one number in the chosen byte order. Clicking a group edits it.
+pub fn memory(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    view: &mut MemoryView,
+) -> Option<MemoryEdit> {
+    if !matches!(state, ControlState::GDBRunning { .. }) {
+        return None;
+    }
+
+    let mut edit = None;
+    let mem = &data.memory;
+
+    let open = std::mem::take(&mut view.reveal).then_some(true);
+    egui::CollapsingHeader::new("Memory")
+        .open(open)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let res = ui.add(
+                    egui::TextEdit::singleline(&mut view.input)
+                        .hint_text("Address or expression")
+                        .font(egui::TextStyle::Monospace),
+                );
+                let entered = res.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                if (ui.button("Read").clicked() || entered) && !view.input.trim().is_empty() {
+                    edit = Some(MemoryEdit::Read(view.input.clone()));
+                }
+
+                if let Some(start) = mem.start {
+                    let mut page = |to: u64| {
+                        view.input = format!("{to:#x}");
+                        edit = Some(MemoryEdit::Read(view.input.clone()));
+                    };
+                    if ui.button("Previous").clicked() {
+                        page(start.saturating_sub(memory::PAGE_SIZE));
+                    }
+                    if ui.button("Next").clicked() {
+                        page(start.saturating_add(memory::PAGE_SIZE));
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                for size in [1, 2, 4, 8] {
+                    ui.selectable_value(&mut view.group, size, format!("{size}"));
+                }
+                ui.separator();
+                ui.selectable_value(&mut view.little_endian, true, "Little endian");
+                ui.selectable_value(&mut view.little_endian, false, "Big endian");
+            });
+
+            if let Some(e) = &mem.error {
+                ui.colored_label(Color32::from_rgb(255, 100, 100), e);
+            }
+
+            let start = match mem.start {
+                Some(start) => start,
+                None => return,
+            };
+
+            egui::Grid::new("memory").striped(true).show(ui, |ui| {
+                for (row, bytes) in mem.contents.chunks(memory::ROW_SIZE).enumerate() {
+                    let row_offset = row * memory::ROW_SIZE;
+                    ui.monospace(format!("{:#018x}", start + row_offset as u64));
+
+                    ui.horizontal(|ui| {
+                        for (g, group) in bytes.chunks(view.group).enumerate() {
+                            let offset = row_offset + g * view.group;
+                            let addr = start + offset as u64;
+                            let w = memory_group(ui, state, mem, view, addr, offset, group);
+                            if let Some(w) = w {
+                                edit = Some(w);
+                            }
+                        }
+                    });
+
+                    ui.monospace(memory::ascii(bytes));
+                    ui.end_row();
+                }
+            });
+        });
+
+    edit
+}
+
+fn memory_group(
+    ui: &mut Ui,
+    state: &ControlState,
+    mem: &memory::Memory,
+    view: &mut MemoryView,
+    addr: u64,
+    offset: usize,
+    group: &[u8],
+) -> Option<MemoryEdit> {
+    let mut edit = None;
+    let shown = memory::group_text(group, view.little_endian);
+
+    match &mut view.editing {
+        Some((a, input)) if *a == addr => {
+            let res = ui.add(
+                egui::TextEdit::singleline(input)
+                    .desired_width(8.0 * shown.len() as f32)
+                    .font(egui::TextStyle::Monospace),
+            );
+
+            // Enter writes it, clicking somewhere else gives up
+            if res.lost_focus() {
+                let parsed = memory::parse_group(input, group.len(), view.little_endian)
+                    .filter(|_| ui.input().key_pressed(egui::Key::Enter));
+                if let Some(bytes) = parsed {
+                    edit = Some(MemoryEdit::Write(addr, bytes));
+                }
+                view.editing = None;
+            } else {
+                res.request_focus();
+            }
+        }
+        _ => {
+            let changed = (offset..offset + group.len()).any(|i| mem.changed(i));
+            let label = Label::new(value_text(&shown, changed)).sense(Sense::click());
+
+            // Same as the registers, a core file can't be written to
+            if ui.add(label).clicked() && state.is_stopped() {
+                view.editing = Some((addr, shown));
+            }
+        }
+    }
+
+    edit
+}
+
+/// Every thread with its state and where it is, under the process it belongs to when there's more
+/// than one. Clicking one selects it, the call stack, variables and registers follow. `locked`
+/// keeps the other threads still while stepping.
+pub fn threads(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    locked: &mut bool,
+) -> Option<ControlState> {
+    let (exec_state, threads) = match (state, &data.threads) {
+        (ControlState::GDBRunning { state, .. }, Some(threads)) => (*state, threads),
+        _ => return None,
+    };
+
+    // In non-stop mode a stopped thread can be looked at while others run
+    let selectable = exec_state != GDBExecutionState::Running || data.non_stop;
+    let mut next = None;
+
+    ui.collapsing("Threads", |ui| {
+        let groups = &data.inferiors.groups;
+        if groups.len() > 1 {
+            for g in groups {
+                let status = match (&g.pid, &g.exit_code) {
+                    (Some(pid), _) => format!("process {pid}"),
+                    (None, Some(code)) => format!("exited with code {code}"),
+                    (None, None) => "not running".to_string(),
+                };
+                let title = format!(
+                    "{} {status} {}",
+                    g.id,
+                    g.executable.as_deref().unwrap_or("")
+                );
+
+                egui::CollapsingHeader::new(RichText::new(title).monospace())
+                    .id_source(("inferior", &g.id))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        if ui
+                            .add_enabled(selectable, egui::Button::new("Switch to it"))
+                            .clicked()
+                        {
+                            next = Some(ControlState::switch_inferior(state, &g.id));
+                        }
+
+                        egui::Grid::new(("threads", &g.id))
+                            .striped(true)
+                            .show(ui, |ui| {
+                                let own = threads
+                                    .iter()
+                                    .filter(|t| data.inferiors.group_of(&t.id) == Some(&g.id));
+                                for t in own {
+                                    let row = thread_row(ui, state, data, selectable, t);
+                                    if let Some(n) = row {
+                                        next = Some(n);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            }
+        } else {
+            egui::Grid::new("threads").striped(true).show(ui, |ui| {
+                for t in threads {
+                    if let Some(n) = thread_row(ui, state, data, selectable, t) {
+                        next = Some(n);
+                    }
+                    ui.end_row();
+                }
+            });
+        }
+
+        if exec_state == GDBExecutionState::PostMortem {
+            return;
+        }
+
+        if ui
+            .checkbox(locked, "Step only the selected thread")
+            .changed()
+        {
+            next = Some(ControlState::lock_scheduler(state, *locked));
+        }
+
+        ui.horizontal(|ui| {
+            for (i, (name, values)) in inferiors::SETTINGS.iter().enumerate() {
+                let current = &data.inferiors.settings[i];
+                ui.label(*name);
+                egui::ComboBox::from_id_source(name)
+                    .selected_text(current.as_str())
+                    .show_ui(ui, |ui| {
+                        for v in *values {
+                            if ui.selectable_label(current == v, *v).clicked() && current != v {
+                                next = Some(ControlState::set_inferior_setting(state, i, v));
+                            }
+                        }
+                    });
+            }
+        });
+    });
+
+    next
+}
+
+fn thread_row(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    selectable: bool,
+    t: &Thread,
+) -> Option<ControlState> {
+    let mut next = None;
+    let selected = data.selected_thread.as_ref() == Some(&t.id);
+    let where_ = match &t.frame {
+        Some(f) => match (&f.file, f.line, f.addr) {
+            (Some(file), Some(line), _) => format!("{} {file}:{line}", f.func),
+            (_, _, Some(addr)) => format!("{} {addr:#018x}", f.func),
+            _ => f.func.clone(),
+        },
+        None => String::new(),
+    };
+
+    let id = ui.add_enabled(
+        selectable,
+        egui::SelectableLabel::new(selected, t.id.as_str()),
+    );
+    ui.monospace(&t.target_id);
+    ui.monospace(t.name.as_deref().unwrap_or(""));
+    ui.monospace(&t.state);
+    ui.monospace(where_);
+    let post_mortem = matches!(
+        state,
+        ControlState::GDBRunning {
+            state: GDBExecutionState::PostMortem,
+            ..
+        }
+    );
+    if data.non_stop && !post_mortem {
+        if t.state == "running" {
+            if ui.button("Interrupt").clicked() {
+                next = Some(ControlState::interrupt_thread(state, &t.id));
+            }
+        } else if ui.button("Continue").clicked() {
+            next = Some(ControlState::continue_thread(state, &t.id));
+        }
+    }
+
+    if id.clicked() && !selected {
+        next = Some(ControlState::select_thread(state, &t.id, data));
+    }
+
+    next
+}
+
+/// Every thread of a core file with its backtrace and the locals of each frame
+pub fn thread_backtraces(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
+    let threads = match (state, &data.threads) {
         (
-            ControlState::GDBRunning { .. },
-            PersistentData {
-                frames: Some(fs), ..
+            ControlState::GDBRunning {
+                state: GDBExecutionState::PostMortem,
+                ..
             },
-        ) => {
-            for f in fs {
-                ui.monospace(format!("{f:?}"));
-                ui.separator();
+            Some(threads),
+        ) => threads,
+        _ => return,
+    };
+
+    for t in threads {
+        let crashed = data.stopped_thread.as_ref() == Some(&t.id);
+        let title = format!(
+            "{} Thread {} {} {}",
+            if crashed { "*" } else { " " },
+            t.id,
+            t.target_id,
+            t.name.as_deref().unwrap_or("")
+        );
+
+        egui::CollapsingHeader::new(RichText::new(title).monospace())
+            .id_source(("thread", &t.id))
+            .default_open(crashed)
+            .show(ui, |ui| {
+                for f in data.thread_frames.get(&t.id).into_iter().flatten() {
+                    egui::CollapsingHeader::new(
+                        RichText::new(format!("#{} {}", f.level, f.func)).monospace(),
+                    )
+                    .id_source(("frame", &t.id, f.level))
+                    .show(ui, |ui| {
+                        let vars = data.frame_variables.get(&(t.id.clone(), f.level));
+                        for v in vars.into_iter().flatten() {
+                            ui.monospace(format!(
+                                "{} = {}",
+                                v.name,
+                                v.value.as_deref().unwrap_or("...")
+                            ));
+                        }
+                    });
+                }
+            });
+    }
+}
+
+/// Where the last crash report was written, or why it couldn't be
+pub fn crash_report(ui: &mut Ui, state: &ControlState, data: &PersistentData) {
+    if !matches!(
+        state,
+        ControlState::GDBRunning { .. } | ControlState::GDBExited { .. }
+    ) {
+        return;
+    }
+
+    if data.report.pending > 0 {
+        ui.label("Generating the crash report...");
+    }
+
+    match &data.report.written {
+        Some(Ok(p)) => ui.monospace(format!("Crash report written to {}", p.display())),
+        Some(Err(e)) => ui.colored_label(
+            Color32::from_rgb(255, 100, 100),
+            format!("Failed to write the crash report: {e}"),
+        ),
+        None => return,
+    };
+}
+
+pub fn exit_status(ui: &mut Ui, state: &ControlState) {
+    if let ControlState::GDBExited { status } = state {
+        let msg = match status {
+            Some(ExitStatus::Normal) => "The program exited normally".to_string(),
+            Some(ExitStatus::Code(code)) => format!("The program exited with code {code}"),
+            Some(ExitStatus::Signal { name, meaning }) => {
+                format!("The program was terminated by {name}, {meaning}")
             }
+            None => "The program was killed".to_string(),
+        };
+
+        ui.label(RichText::new(msg).color(Color32::from_rgb(255, 155, 155)));
+    }
+}
+
+/// Lists the local processes matching `filter`, clicking one attaches to it
+pub fn process_picker(
+    ui: &mut Ui,
+    state: &ControlState,
+    filter: &str,
+    processes: &mut procs::ProcessList,
+) -> Option<ControlState> {
+    let mut next = None;
+
+    if let ControlState::AttachProcessDialog { pid: None, error } = state {
+        if let Some(e) = error {
+            ui.label(RichText::new(e).color(Color32::from_rgb(255, 155, 155)));
+        }
+
+        let now = ui.button("Refresh").clicked();
+        processes.refresh(now);
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("processes").striped(true).show(ui, |ui| {
+                    ui.strong("PID");
+                    ui.strong("User");
+                    ui.strong("Command line");
+                    ui.end_row();
+
+                    for p in processes.processes.iter().filter(|p| p.matches(filter)) {
+                        let row = ui.add(
+                            Label::new(RichText::new(p.pid.to_string()).monospace())
+                                .sense(Sense::click()),
+                        );
+                        ui.monospace(&p.user);
+                        ui.monospace(&p.cmdline);
+                        ui.end_row();
+
+                        if row.on_hover_text("Attach to this process").clicked() {
+                            next = Some(ControlState::AttachProcessDialog {
+                                pid: Some(p.pid),
+                                error: None,
+                            });
+                        }
+                    }
+                });
+            });
+    }
+
+    next
+}
+
+/// What QEMU printed on its serial port or the program under gdbserver on its stdout, the line
+/// typed below goes to it
+pub fn target_console(
+    ui: &mut Ui,
+    title: &str,
+    output: &str,
+    input: &mut String,
+) -> Option<String> {
+    let mut sent = None;
+
+    egui::CollapsingHeader::new(title)
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .stick_to_bottom()
+                .show(ui, |ui| {
+                    ui.add(Label::new(RichText::new(output).monospace()).wrap(true));
+                });
+
+            ui.horizontal(|ui| {
+                let edit = ui.text_edit_singleline(input);
+                let enter = edit.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                if ui.button("Send").clicked() || enter {
+                    sent = Some(std::mem::take(input));
+                    edit.request_focus();
+                }
+            });
+        });
+
+    sent
+}
+
+/// Fills the architecture field of the kernel dialog, anything else GDB knows can be typed in
+pub fn architecture_picker(ui: &mut Ui, state: &ControlState, field: &mut String) {
+    if !matches!(state, ControlState::TryAttachPort { config: None }) {
+        return;
+    }
+
+    let selected = if field.is_empty() {
+        kernel::ARCHITECTURES[0]
+    } else {
+        field.as_str()
+    };
+    let mut choice = None;
+
+    egui::ComboBox::from_label("Pick an architecture")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for arch in kernel::ARCHITECTURES {
+                if ui.selectable_label(selected == *arch, *arch).clicked() {
+                    choice = Some(arch.to_string());
+                }
+            }
+        });
+
+    if let Some(c) = choice {
+        *field = c;
+    }
+}
+
+/// The standard VGA-ish colours for ANSI 0 to 15
+const ANSI_COLORS: [Color32; 16] = [
+    Color32::from_rgb(0, 0, 0),
+    Color32::from_rgb(205, 49, 49),
+    Color32::from_rgb(13, 188, 121),
+    Color32::from_rgb(229, 229, 16),
+    Color32::from_rgb(36, 114, 200),
+    Color32::from_rgb(188, 63, 188),
+    Color32::from_rgb(17, 168, 205),
+    Color32::from_rgb(229, 229, 229),
+    Color32::from_rgb(102, 102, 102),
+    Color32::from_rgb(241, 76, 76),
+    Color32::from_rgb(35, 209, 139),
+    Color32::from_rgb(245, 245, 67),
+    Color32::from_rgb(59, 142, 234),
+    Color32::from_rgb(214, 112, 214),
+    Color32::from_rgb(41, 184, 219),
+    Color32::from_rgb(255, 255, 255),
+];
+
+/// The program's terminal, there while a session is. Clicking it gives it the keyboard, the keys
+/// typed are returned as the bytes a terminal would send.
+pub fn terminal(
+    ui: &mut Ui,
+    session: bool,
+    screen: &terminal::Screen,
+    view: &mut terminal::TerminalView,
+) -> Vec<u8> {
+    if !session {
+        view.focused = false;
+        return vec![];
+    }
+
+    let mut keys = vec![];
+
+    egui::CollapsingHeader::new("Program terminal")
+        .default_open(true)
+        .show(ui, |ui| {
+            let focused = &mut view.focused;
+            let job = match &view.job {
+                Some((version, f, job)) if *version == screen.version && f == focused => {
+                    job.clone()
+                }
+                _ => {
+                    let font = egui::TextStyle::Monospace.resolve(ui.style());
+                    let job = terminal_job(screen, *focused, font);
+                    view.job = Some((screen.version, *focused, job.clone()));
+                    job
+                }
+            };
+
+            let frame = egui::Frame::dark_canvas(ui.style()).stroke(if *focused {
+                ui.visuals().selection.stroke
+            } else {
+                ui.visuals().widgets.noninteractive.bg_stroke
+            });
+            let response = frame
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .stick_to_bottom()
+                        .show(ui, |ui| {
+                            ui.set_min_width(ui.available_width());
+                            ui.add(Label::new(job).wrap(false).sense(Sense::click()))
+                        })
+                        .inner
+                })
+                .inner;
+
+            if response.clicked() {
+                *focused = true;
+            } else if ui.input().pointer.any_click() && !response.hovered() {
+                *focused = false;
+            }
+
+            if *focused {
+                keys = terminal_keys(&ui.input().events);
+                ui.label("Typing goes to the program, click elsewhere to stop");
+            } else {
+                ui.label("Click the terminal to type into the program");
+            }
+        });
+
+    keys
+}
+
+/// The screen as one text, each run of cells drawn alike is a single section
+fn terminal_job(
+    screen: &terminal::Screen,
+    focused: bool,
+    font: egui::FontId,
+) -> egui::text::LayoutJob {
+    // Text and background colour
+    fn append(
+        job: &mut egui::text::LayoutJob,
+        run: &mut String,
+        colors: (Color32, Color32),
+        font: &egui::FontId,
+    ) {
+        if run.is_empty() {
+            return;
+        }
+
+        let format = egui::TextFormat {
+            font_id: font.clone(),
+            color: colors.0,
+            background: colors.1,
+            ..Default::default()
+        };
+        job.append(&std::mem::take(run), 0.0, format);
+    }
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut run = String::new();
+    let mut colors = (Color32::LIGHT_GRAY, Color32::TRANSPARENT);
+
+    for (i, line) in screen.lines.iter().enumerate() {
+        let cursor = (focused && i == screen.row).then_some(screen.col);
+        let len = line.len().max(cursor.map_or(0, |c| c + 1));
+
+        for j in 0..len {
+            let cell = line.get(j).copied().unwrap_or_default();
+            let next = if cursor == Some(j) {
+                (Color32::BLACK, Color32::LIGHT_GRAY)
+            } else {
+                let color = match cell.fg {
+                    Some(c) => ANSI_COLORS[c as usize],
+                    None if cell.bold => Color32::WHITE,
+                    None => Color32::LIGHT_GRAY,
+                };
+                (color, Color32::TRANSPARENT)
+            };
+
+            if next != colors {
+                append(&mut job, &mut run, colors, &font);
+                colors = next;
+            }
+            run.push(cell.ch);
+        }
+
+        if i + 1 < screen.lines.len() {
+            // The cursor's background stops at the end of the line
+            if colors.1 != Color32::TRANSPARENT {
+                append(&mut job, &mut run, colors, &font);
+                colors.1 = Color32::TRANSPARENT;
+            }
+            run.push('\n');
+        }
+    }
+    append(&mut job, &mut run, colors, &font);
+
+    job
+}
+
+/// What a VT100 sends for the keys pressed
+fn terminal_keys(events: &[egui::Event]) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for event in events {
+        match event {
+            egui::Event::Text(t) => bytes.extend_from_slice(t.as_bytes()),
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+            } => {
+                let seq: &[u8] = match key {
+                    egui::Key::Enter => b"\r",
+                    egui::Key::Backspace => b"\x7f",
+                    egui::Key::Tab => b"\t",
+                    egui::Key::Escape => b"\x1b",
+                    egui::Key::ArrowUp => b"\x1b[A",
+                    egui::Key::ArrowDown => b"\x1b[B",
+                    egui::Key::ArrowRight => b"\x1b[C",
+                    egui::Key::ArrowLeft => b"\x1b[D",
+                    egui::Key::Home => b"\x1b[H",
+                    egui::Key::End => b"\x1b[F",
+                    egui::Key::Delete => b"\x1b[3~",
+                    // Ctrl+C, Ctrl+D, Ctrl+Z...
+                    k if modifiers.ctrl => {
+                        let name = format!("{k:?}");
+                        match name.as_bytes() {
+                            [c @ b'A'..=b'Z'] => {
+                                bytes.push(c - b'A' + 1);
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+                bytes.extend_from_slice(seq);
+            }
+            _ => {}
+        }
+    }
+
+    bytes
+}
+
+fn stream_color(stream: Stream) -> Color32 {
+    match stream {
+        Stream::Console => Color32::LIGHT_GRAY,
+        Stream::Target => Color32::from_rgb(120, 200, 120),
+        Stream::Log => Color32::from_rgb(220, 200, 100),
+        Stream::Result => Color32::from_rgb(130, 130, 130),
+        Stream::Error => Color32::from_rgb(255, 100, 100),
+        Stream::Async => Color32::from_rgb(180, 140, 220),
+        Stream::Other => Color32::from_rgb(160, 160, 160),
+    }
+}
+
+/// The commands we sent and what GDB answered to each, the records can be filtered by stream and
+/// searched
+pub fn console(ui: &mut Ui, transcript: &Transcript, view: &mut ConsoleView) {
+    ui.horizontal_wrapped(|ui| {
+        for ((stream, name), shown) in STREAMS.iter().zip(view.shown.iter_mut()) {
+            ui.checkbox(shown, RichText::new(*name).color(stream_color(*stream)));
+        }
+        ui.separator();
+        ui.checkbox(&mut view.raw, "Show raw MI");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Search");
+        ui.text_edit_singleline(&mut view.search);
+    });
+
+    egui::ScrollArea::vertical()
+        .max_height(400.0)
+        .max_width(f32::INFINITY)
+        .stick_to_bottom()
+        .show(ui, |ui| {
+            for block in &transcript.blocks {
+                let records = match view.visible(block) {
+                    Some(records) => records,
+                    None => continue,
+                };
+
+                if let Some(cmd) = &block.command {
+                    ui.add_space(4.0);
+                    ui.label(
+                        RichText::new(format!("> {cmd}"))
+                            .monospace()
+                            .strong()
+                            .color(Color32::from_rgb(120, 170, 255)),
+                    );
+                }
+
+                for r in records {
+                    let text = view.text(r);
+                    let text = if view.raw {
+                        text
+                    } else {
+                        text.strip_suffix('\n').unwrap_or(text)
+                    };
+                    ui.label(
+                        RichText::new(text)
+                            .monospace()
+                            .color(stream_color(r.stream)),
+                    );
+                }
+            }
+        });
+}
+
+/// The console's input, up and down go through the history and tab asks GDB for completions.
+/// Returns the command to send.
+pub fn command_line(ui: &mut Ui, line: &mut CommandLine) -> Option<String> {
+    let mut cmd = None;
+
+    ui.horizontal(|ui| {
+        let edit = ui.add(
+            egui::TextEdit::singleline(&mut line.input)
+                .hint_text("Write something")
+                .font(egui::TextStyle::Monospace)
+                .lock_focus(true),
+        );
+
+        if edit.has_focus() {
+            let input = ui.input();
+            if input.key_pressed(egui::Key::ArrowUp) {
+                line.up();
+            } else if input.key_pressed(egui::Key::ArrowDown) {
+                line.down();
+            } else if input.key_pressed(egui::Key::Tab) {
+                cmd = line.complete_command();
+            }
+        }
+
+        let enter = edit.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+        if ui.button("Send command").clicked() || enter {
+            cmd = line.submit();
+            edit.request_focus();
+        }
+
+        ui.checkbox(&mut line.cli, "CLI mode")
+            .on_hover_text("Run plain commands with -interpreter-exec console");
+    });
+
+    if !line.matches.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            for m in line.matches.clone() {
+                let label = Label::new(RichText::new(&m).monospace()).sense(Sense::click());
+                if ui.add(label).clicked() {
+                    line.input = m;
+                    line.matches.clear();
+                }
+            }
+        });
+    }
+
+    cmd
+}
+
+/// Expressions evaluated in the selected frame, newest last, with their results shown by type
+pub fn repl(
+    ui: &mut Ui,
+    state: &ControlState,
+    data: &PersistentData,
+    view: &mut ReplView,
+) -> Option<ReplEdit> {
+    if !matches!(state, ControlState::GDBRunning { .. }) {
+        return None;
+    }
+
+    let mut edit = None;
+
+    ui.collapsing("Evaluate", |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(400.0)
+            .stick_to_bottom()
+            .show(ui, |ui| {
+                for entry in &data.repl.entries {
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("> {}", entry.expression));
+                        if let Some(t) = &entry.var_type {
+                            ui.label(RichText::new(t).color(Color32::from_rgb(130, 130, 130)));
+                        }
+                        if entry.format != repl::FORMATS[0] {
+                            ui.label(
+                                RichText::new(format!("/{}", entry.format))
+                                    .color(Color32::from_rgb(130, 130, 130)),
+                            );
+                        }
+                    });
+
+                    match &entry.result {
+                        Some(Ok(v)) => repl_value(ui, v, &format!("repl{}", entry.id), &mut edit),
+                        Some(Err(e)) => {
+                            ui.colored_label(Color32::from_rgb(255, 100, 100), e);
+                        }
+                        None => {
+                            ui.label("...");
+                        }
+                    }
+                    ui.add_space(4.0);
+                }
+            });
+
+        ui.horizontal(|ui| {
+            let res = ui.add(
+                egui::TextEdit::singleline(&mut view.input)
+                    .hint_text("Expression")
+                    .font(egui::TextStyle::Monospace),
+            );
+
+            egui::ComboBox::from_id_source("repl_format")
+                .selected_text(repl::FORMATS[view.format])
+                .show_ui(ui, |ui| {
+                    for (i, f) in repl::FORMATS.iter().enumerate() {
+                        ui.selectable_value(&mut view.format, i, *f);
+                    }
+                });
+
+            let entered = res.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+            if (ui.button("Evaluate").clicked() || entered) && !view.input.trim().is_empty() {
+                edit = Some(ReplEdit::Evaluate {
+                    expression: std::mem::take(&mut view.input).trim().to_string(),
+                    format: repl::FORMATS[view.format].to_string(),
+                });
+                res.request_focus();
+            }
+            if ui.button("Clear").clicked() {
+                edit = Some(ReplEdit::Clear);
+            }
+        });
+    });
+
+    edit
+}
+
+/// Structs are trees, arrays are tables and pointers open the memory view
+fn repl_value(ui: &mut Ui, value: &Value, id: &str, edit: &mut Option<ReplEdit>) {
+    match value {
+        Value::Struct(fields) => {
+            egui::CollapsingHeader::new("{...}")
+                .id_source(id)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for (name, v) in fields {
+                        ui.horizontal(|ui| {
+                            ui.monospace(format!("{name} ="));
+                            repl_value(ui, v, &format!("{id}.{name}"), edit);
+                        });
+                    }
+                });
+        }
+        Value::Array(items) => {
+            egui::CollapsingHeader::new(format!("[{}]", items.len()))
+                .id_source(id)
+                .default_open(items.len() <= 16)
+                .show(ui, |ui| {
+                    egui::Grid::new(id).striped(true).show(ui, |ui| {
+                        for (i, v) in items.iter().enumerate() {
+                            ui.monospace(format!("[{i}]"));
+                            repl_value(ui, v, &format!("{id}[{i}]"), edit);
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+        Value::Text(s) => {
+            ui.label(
+                RichText::new(s)
+                    .monospace()
+                    .color(Color32::from_rgb(120, 200, 120)),
+            );
+        }
+        Value::Pointer { address, text } => {
+            let label = Label::new(
+                RichText::new(text)
+                    .monospace()
+                    .underline()
+                    .color(Color32::from_rgb(120, 170, 255)),
+            )
+            .sense(Sense::click());
+            if ui.add(label).on_hover_text("Show in memory").clicked() {
+                *edit = Some(ReplEdit::ShowMemory(*address));
+            }
+        }
+        Value::Scalar(s) => {
+            ui.monospace(s);
         }
-        _ => {}
     }
 }
@@ -0,0 +1,273 @@
+use crate::mi_types::{VarChange, VarObj, Variable};
+use std::collections::HashMap;
+
+/// A variable object and what we know about its children
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    pub obj: VarObj,
+    /// Names of the children, `None` until they are listed
+    pub children: Option<Vec<String>>,
+    /// Its value changed on the last `-var-update`
+    pub changed: bool,
+}
+
+/// The frame variable objects are created in, as thread id, frame address and function. That's how
+/// GDB tells frames apart: the level doesn't change when a recursive call is stepped into, and the
+/// depth doesn't either when a call returns and the next one is stepped into.
+pub type FrameKey = (String, u64, String);
+
+/// The variable objects we asked GDB for. They are kept across stops so `-var-update` only has to
+/// tell what changed, and are deleted when their frame goes away.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct VarObjCache {
+    pub nodes: HashMap<String, Node>,
+    /// One per variable of the frame, in the order GDB listed them
+    pub roots: Vec<String>,
+    pub frame: Option<FrameKey>,
+    next_id: u32,
+}
+
+impl VarObjCache {
+    /// The commands that make the roots match the variables of `frame`. Nothing to do when it's
+    /// the same frame as before with the same variables.
+    pub fn sync(&mut self, frame: Option<FrameKey>, vars: &[Variable]) -> Vec<String> {
+        let mut names: Vec<&str> = vec![];
+        for v in vars {
+            // Shadowed variables have the same name, only the innermost can be evaluated
+            if !names.contains(&v.name.as_str()) {
+                names.push(&v.name);
+            }
+        }
+
+        let current = self
+            .roots
+            .iter()
+            .filter_map(|r| self.nodes.get(r)?.obj.expression.as_deref())
+            .collect::<Vec<_>>();
+        if self.frame == frame && current == names {
+            return vec![];
+        }
+
+        let mut cmds = self
+            .roots
+            .drain(..)
+            .map(|r| format!("-var-delete {r}"))
+            .collect::<Vec<_>>();
+        self.nodes.clear();
+        self.frame = frame;
+
+        if self.frame.is_none() {
+            return cmds;
+        }
+
+        for name in names {
+            self.next_id += 1;
+            let id = format!("v{}", self.next_id);
+
+            cmds.push(format!("-var-create {id} * {name}"));
+            self.nodes.insert(
+                id.clone(),
+                Node {
+                    obj: VarObj {
+                        name: id.clone(),
+                        expression: Some(name.to_string()),
+                        var_type: None,
+                        value: None,
+                        numchild: 0,
+                    },
+                    children: None,
+                    changed: false,
+                },
+            );
+            self.roots.push(id);
+        }
+
+        cmds
+    }
+
+    /// The answer to `-var-create`, the expression is the one we created it with
+    pub fn created(&mut self, obj: VarObj) {
+        if let Some(node) = self.nodes.get_mut(&obj.name) {
+            node.obj = VarObj {
+                expression: node.obj.expression.take(),
+                ..obj
+            };
+        }
+    }
+
+    /// The answer to `-var-list-children`. Children are named after their parent, `v1.x` is the
+    /// field `x` of `v1`, that's how we know where they go.
+    pub fn add_children(&mut self, children: Vec<VarObj>) {
+        for child in children {
+            let parent = child
+                .expression
+                .as_ref()
+                .and_then(|exp| child.name.strip_suffix(exp.as_str())?.strip_suffix('.'))
+                .map(String::from);
+
+            let siblings = parent
+                .and_then(|p| self.nodes.get_mut(&p))
+                .map(|p| p.children.get_or_insert_with(Vec::new));
+
+            match siblings {
+                Some(siblings) if !siblings.contains(&child.name) => {
+                    siblings.push(child.name.clone())
+                }
+                Some(_) => {}
+                None => continue,
+            }
+
+            self.nodes.insert(
+                child.name.clone(),
+                Node {
+                    obj: child,
+                    children: None,
+                    changed: false,
+                },
+            );
+        }
+    }
+
+    /// The answer to `-var-update`, returns the commands to delete the objects GDB can't use anymore
+    pub fn update(&mut self, changes: Vec<VarChange>) -> Vec<String> {
+        for node in self.nodes.values_mut() {
+            node.changed = false;
+        }
+
+        let mut cmds = vec![];
+        for c in changes {
+            if c.in_scope == "invalid" {
+                self.remove(&c.name);
+                cmds.push(format!("-var-delete {}", c.name));
+                continue;
+            }
+
+            let node = match self.nodes.get_mut(&c.name) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            node.obj.value = if c.in_scope == "true" { c.value } else { None };
+            node.changed = true;
+
+            // Its children aren't the same anymore
+            if c.new_type.is_some() || c.new_numchild.is_some() {
+                node.obj.var_type = c.new_type.or(node.obj.var_type.take());
+                node.obj.numchild = c.new_numchild.unwrap_or(node.obj.numchild);
+                self.remove_children(&c.name);
+            }
+        }
+
+        cmds
+    }
+
+    /// Asks for the children of `name`, they are listed once
+    pub fn list_children_command(name: &str) -> String {
+        format!("-var-list-children --all-values {name}")
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.remove_children(name);
+        self.nodes.remove(name);
+        self.roots.retain(|r| r != name);
+    }
+
+    fn remove_children(&mut self, name: &str) {
+        let prefix = format!("{name}.");
+        self.nodes.retain(|n, _| !n.starts_with(&prefix));
+
+        if let Some(node) = self.nodes.get_mut(name) {
+            node.children = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Variable {
+        Variable {
+            name: name.to_string(),
+            var_type: None,
+            value: None,
+            arg: false,
+        }
+    }
+
+    fn obj(name: &str, exp: Option<&str>, numchild: u32) -> VarObj {
+        VarObj {
+            name: name.to_string(),
+            expression: exp.map(String::from),
+            var_type: Some("int".to_string()),
+            value: Some("1".to_string()),
+            numchild,
+        }
+    }
+
+    #[test]
+    fn test_sync() {
+        let mut cache = VarObjCache::default();
+        let frame = Some(("1".to_string(), 0x7ffe0, "main".to_string()));
+
+        let cmds = cache.sync(frame.clone(), &[var("a"), var("p"), var("a")]);
+        assert_eq!(cmds, vec!["-var-create v1 * a", "-var-create v2 * p"]);
+
+        // Same frame and variables, the objects are kept
+        assert!(cache.sync(frame, &[var("a"), var("p")]).is_empty());
+
+        let cmds = cache.sync(
+            Some(("1".to_string(), 0x7ffc0, "fib".to_string())),
+            &[var("n")],
+        );
+        assert_eq!(
+            cmds,
+            vec!["-var-delete v1", "-var-delete v2", "-var-create v3 * n"]
+        );
+
+        let cmds = cache.sync(None, &[]);
+        assert_eq!(cmds, vec!["-var-delete v3"]);
+        assert!(cache.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_children_and_update() {
+        let mut cache = VarObjCache::default();
+        cache.sync(
+            Some(("1".to_string(), 0x7ffe0, "main".to_string())),
+            &[var("p")],
+        );
+
+        cache.created(obj("v1", None, 2));
+        assert_eq!(cache.nodes["v1"].obj.expression, Some("p".to_string()));
+
+        cache.add_children(vec![obj("v1.x", Some("x"), 0), obj("v1.y", Some("y"), 0)]);
+        assert_eq!(
+            cache.nodes["v1"].children,
+            Some(vec!["v1.x".to_string(), "v1.y".to_string()])
+        );
+
+        let cmds = cache.update(vec![VarChange {
+            name: "v1.x".to_string(),
+            value: Some("3".to_string()),
+            in_scope: "true".to_string(),
+            new_type: None,
+            new_numchild: None,
+        }]);
+        assert!(cmds.is_empty());
+        assert!(cache.nodes["v1.x"].changed);
+        assert!(!cache.nodes["v1.y"].changed);
+        assert_eq!(cache.nodes["v1.x"].obj.value, Some("3".to_string()));
+
+        let cmds = cache.update(vec![VarChange {
+            name: "v1".to_string(),
+            value: None,
+            in_scope: "invalid".to_string(),
+            new_type: None,
+            new_numchild: None,
+        }]);
+        assert_eq!(cmds, vec!["-var-delete v1"]);
+        assert!(cache.nodes.is_empty());
+        assert!(cache.roots.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::config;
+use crate::mi::escape;
+use crate::tokens::{self, WATCHPOINTS, WATCH_VALUES};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Watches are never given an id that was used before, see `Watch::id`
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// An expression evaluated again on every stop
+#[derive(Clone, PartialEq, Debug)]
+pub struct Watch {
+    /// Answers are sent back with a token made of it, so one still on its way when the watch is
+    /// removed can't land on another watch
+    pub id: u64,
+    pub expression: String,
+    /// The value, or GDB's error message. `None` until it's evaluated
+    pub value: Option<Result<String, String>>,
+    /// Number of the watchpoint that stops when the expression changes, or why it couldn't be set
+    pub watchpoint: Option<Result<String, String>>,
+}
+
+impl Watch {
+    pub fn new(expression: &str) -> Watch {
+        Watch {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            expression: expression.trim().to_string(),
+            value: None,
+            watchpoint: None,
+        }
+    }
+}
+
+/// What can be done to the watch list from the UI
+#[derive(Clone, PartialEq, Debug)]
+pub enum WatchEdit {
+    Add(String),
+    Remove(usize),
+    Watchpoint(usize),
+}
+
+/// The saved expressions, or none if there aren't any
+pub fn load() -> Vec<Watch> {
+    config::read("watches")
+        .map(|s| parse(&s))
+        .unwrap_or_default()
+}
+
+pub fn save(watches: &[Watch]) -> std::io::Result<()> {
+    config::write("watches", &serialize(watches))
+}
+
+/// Changes the list and returns the commands that go with it
+pub fn apply(watches: &mut Vec<Watch>, edit: WatchEdit) -> Vec<String> {
+    match edit {
+        WatchEdit::Add(expression) => {
+            if expression.trim().is_empty() {
+                return vec![];
+            }
+            let watch = Watch::new(&expression);
+            let cmd = evaluate_command(&watch);
+            watches.push(watch);
+            vec![cmd]
+        }
+        WatchEdit::Remove(i) if i < watches.len() => match watches.remove(i).watchpoint {
+            Some(Ok(n)) => vec![format!("-break-delete {n}")],
+            _ => vec![],
+        },
+        WatchEdit::Watchpoint(i) if i < watches.len() => {
+            let w = &watches[i];
+            vec![format!(
+                "{}-break-watch \"{}\"",
+                tokens::nth(WATCHPOINTS, w.id),
+                escape(&w.expression)
+            )]
+        }
+        _ => vec![],
+    }
+}
+
+/// GDB deleted watchpoint `number`, its watch can be made one again
+pub fn watchpoint_deleted(watches: &mut [Watch], number: &str) {
+    for w in watches {
+        if matches!(&w.watchpoint, Some(Ok(n)) if n == number) {
+            w.watchpoint = None;
+        }
+    }
+}
+
+/// A new GDB has no watchpoints, until "Reload" sets them again
+pub fn forget_watchpoints(watches: &mut [Watch]) {
+    for w in watches {
+        w.watchpoint = None;
+    }
+}
+
+fn evaluate_command(w: &Watch) -> String {
+    format!(
+        "{}-data-evaluate-expression \"{}\"",
+        tokens::nth(WATCH_VALUES, w.id),
+        escape(&w.expression)
+    )
+}
+
+/// Evaluates every watch, they are sent on every stop
+pub fn evaluate_commands(watches: &[Watch]) -> Vec<String> {
+    watches.iter().map(evaluate_command).collect()
+}
+
+/// Puts an answer in its place, `answer` is the value (or the watchpoint number) or the error
+pub fn answered(watches: &mut [Watch], token: u64, answer: Result<String, String>) {
+    let value = watches
+        .iter_mut()
+        .find(|w| tokens::nth(WATCH_VALUES, w.id) == token);
+    if let Some(w) = value {
+        w.value = Some(answer);
+        return;
+    }
+
+    let watchpoint = watches
+        .iter_mut()
+        .find(|w| tokens::nth(WATCHPOINTS, w.id) == token);
+    if let Some(w) = watchpoint {
+        w.watchpoint = Some(answer);
+    }
+}
+
+fn serialize(watches: &[Watch]) -> String {
+    watches
+        .iter()
+        .map(|w| format!("{}\n", w.expression))
+        .collect()
+}
+
+fn parse(src: &str) -> Vec<Watch> {
+    src.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(Watch::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut watches = parse(&serialize(&[Watch::new("a*2"), Watch::new("arr[i]")]));
+        assert_eq!(watches.len(), 2);
+        let value = |w: &Watch| tokens::nth(WATCH_VALUES, w.id);
+        let watchpoint = |w: &Watch| tokens::nth(WATCHPOINTS, w.id);
+
+        let cmds = apply(&mut watches, WatchEdit::Add("s == \"x\"".to_string()));
+        assert_eq!(
+            cmds,
+            vec![format!(
+                r#"{}-data-evaluate-expression "s == \"x\"""#,
+                value(&watches[2])
+            )]
+        );
+
+        let cmds = apply(&mut watches, WatchEdit::Watchpoint(2));
+        assert_eq!(
+            cmds,
+            vec![format!(
+                r#"{}-break-watch "s == \"x\"""#,
+                watchpoint(&watches[2])
+            )]
+        );
+
+        let (one, two) = (value(&watches[1]), value(&watches[2]));
+        let one_watchpoint = watchpoint(&watches[1]);
+        answered(&mut watches, one, Ok("3".to_string()));
+        answered(
+            &mut watches,
+            two,
+            Err("No symbol \"s\" in current context.".into()),
+        );
+        answered(&mut watches, one_watchpoint, Ok("2".to_string()));
+        assert_eq!(watches[1].value, Some(Ok("3".to_string())));
+        assert!(matches!(watches[2].value, Some(Err(_))));
+        assert_eq!(watches[1].watchpoint, Some(Ok("2".to_string())));
+
+        // Answers to a removed watch don't land on the one that took its place
+        let removed = value(&watches[0]);
+        let cmds = apply(&mut watches, WatchEdit::Remove(0));
+        assert!(cmds.is_empty());
+        assert_eq!(watches[0].expression, "arr[i]");
+        answered(&mut watches, removed, Ok("7".to_string()));
+        assert_eq!(watches[0].value, Some(Ok("3".to_string())));
+
+        // Its watchpoint goes away with it
+        let cmds = apply(&mut watches, WatchEdit::Remove(0));
+        assert_eq!(cmds, vec!["-break-delete 2"]);
+    }
+
+    #[test]
+    fn test_watchpoint_deleted() {
+        let mut watches = vec![Watch::new("a"), Watch::new("b")];
+        let ids = [watches[0].id, watches[1].id];
+        answered(
+            &mut watches,
+            tokens::nth(WATCHPOINTS, ids[0]),
+            Ok("2".into()),
+        );
+        answered(
+            &mut watches,
+            tokens::nth(WATCHPOINTS, ids[1]),
+            Ok("3".into()),
+        );
+
+        // Out of scope, removing the watch mustn't delete whatever gets number 2 next
+        watchpoint_deleted(&mut watches, "2");
+        assert_eq!(watches[0].watchpoint, None);
+        assert_eq!(watches[1].watchpoint, Some(Ok("3".into())));
+        assert!(apply(&mut watches, WatchEdit::Remove(0)).is_empty());
+
+        forget_watchpoints(&mut watches);
+        assert_eq!(watches[0].watchpoint, None);
+    }
+}